@@ -1,14 +1,17 @@
+mod expr_info;
 mod expression_info;
 
+pub use expr_info::ExprInfo;
 pub(crate) use expression_info::ExpressionInfo;
 
 use crate::database::Database;
 use crate::nfagraph::{make_nfa_builder, Ng, NgHolder};
 use crate::parser::{
-    make_glushkov_build_state, parse, prefilter_tree, shortcut_literal, Component, ParseMode,
+    capture_names, check_unsupported, make_glushkov_build_state, parse, parse_combination,
+    prefilter_tree, replace_epsilons, shortcut_literal, Component, ParseMode,
 };
 use crate::rose::RoseEngine;
-use crate::util::ReportId;
+use crate::util::{CompileContext, ReportId, ReportManager};
 use crate::{CompileError, ErrorKind};
 use crate::{Flags, SomType};
 use std::pin::Pin;
@@ -28,13 +31,19 @@ impl ParsedExpression {
         let mut expr = ExpressionInfo {
             index,
             report: id,
-            allow_vacuous: false,
+            allow_vacuous: flags.contains(Flags::ALLOWEMPTY),
             highlander: flags.contains(Flags::SINGLEMATCH),
             utf8: false,
             prefilter: flags.contains(Flags::PREFILTER),
             som: SomType::None,
             quiet: flags.contains(Flags::QUIET),
         };
+        if flags.contains(Flags::SINGLEMATCH | Flags::SOM_LEFTMOST) {
+            return Err(CompileError::new(
+                ErrorKind::Other,
+                "SINGLEMATCH is not supported in combination with SOM_LEFTMOST.",
+            ));
+        }
         if flags.contains(Flags::QUIET | Flags::SOM_LEFTMOST) {
             return Err(CompileError::new(
                 ErrorKind::Other,
@@ -52,6 +61,40 @@ impl ParsedExpression {
     }
 }
 
+/// Parses an expression and returns information about it.
+pub(crate) fn expression_info(expression: &str, flags: Flags) -> Result<ExprInfo, CompileError> {
+    if flags.contains(Flags::COMBINATION) {
+        check_combination_flags(flags)?;
+        parse_combination(expression)?;
+        return Ok(ExprInfo::default());
+    }
+
+    let mut pe = ParsedExpression::new(0, expression, flags, 0)?;
+    let capture_names = capture_names(&pe.component);
+
+    // Apply prefiltering transformations if desired.
+    if pe.expr.prefilter {
+        let mut mode = ParseMode::new(flags);
+        mode.utf8 = pe.expr.utf8;
+        prefilter_tree(&mut pe.component, mode);
+    }
+
+    check_unsupported(&pe.component)?;
+
+    Ok(ExprInfo { capture_names })
+}
+
+/// Checks that a logical combination only has the flags that apply to one.
+fn check_combination_flags(flags: Flags) -> Result<(), CompileError> {
+    if !(Flags::COMBINATION | Flags::QUIET | Flags::SINGLEMATCH).contains(flags) {
+        return Err(CompileError::new(
+            ErrorKind::Other,
+            "Only QUIET and SINGLEMATCH are supported in combination with COMBINATION.",
+        ));
+    }
+    Ok(())
+}
+
 pub(crate) struct BuiltExpression {
     pub(crate) expr: ExpressionInfo,
     pub(crate) g: NgHolder,
@@ -73,33 +116,61 @@ pub(crate) fn add_expression(
         ));
     }
 
+    // Logical combinations are evaluated at scan time from the matches of
+    // the expressions they refer to.
+    if flags.contains(Flags::COMBINATION) {
+        check_combination_flags(flags)?;
+        unsafe {
+            let inner = ng.as_mut().get_unchecked_mut();
+            inner.rm.pl.parse_logical_combination(
+                index,
+                id,
+                expression,
+                flags.contains(Flags::QUIET),
+            )?;
+        }
+        return Ok(());
+    }
+
     let mut pe = ParsedExpression::new(index, expression, flags, id)?;
 
     // Apply prefiltering transformations if desired.
     if pe.expr.prefilter {
-        prefilter_tree(&pe.component);
+        let mut mode = ParseMode::new(flags);
+        mode.utf8 = pe.expr.utf8;
+        prefilter_tree(&mut pe.component, mode);
     }
 
+    // Bail if there's anything in the tree we can't handle.
+    check_unsupported(&pe.component)?;
+
     // If this expression is a literal, we can feed it directly to Rose rather
     // than building the NFA graph.
     if shortcut_literal(ng, &pe)? {
         return Ok(());
     }
 
-    let build_expr = build_graph(&mut pe);
-
     unsafe {
-        let inner: Pin<&mut Ng> = ng.as_mut();
-        inner
-            .get_unchecked_mut()
-            .add_graph(&build_expr.expr, &build_expr.g);
+        let inner = ng.as_mut().get_unchecked_mut();
+        let cc = inner.cc;
+        let build_expr = build_graph(&mut inner.rm, cc, &mut pe)?;
+
+        // Pattern that matches the empty buffer, at every offset.
+        if !build_expr.expr.allow_vacuous && build_expr.g.matches_everywhere() {
+            return Err(CompileError::new(
+                ErrorKind::Other,
+                "Pattern matches empty buffer; use ALLOWEMPTY to enable support.",
+            ));
+        }
+
+        inner.add_graph(&build_expr.expr, build_expr.g)?;
     };
 
     Ok(())
 }
 
 fn generate_rose_engine(ng: &Ng) -> RoseEngine {
-    ng.rose.build_rose()
+    ng.rose.build_rose(ng.min_width)
 }
 
 fn db_create(rose: RoseEngine) -> Database {
@@ -112,13 +183,37 @@ pub(crate) fn build(ng: &Ng) -> Database {
     db_create(rose)
 }
 
-pub(crate) fn build_graph(pe: &mut ParsedExpression) -> BuiltExpression {
-    let mut builder = make_nfa_builder(pe);
+pub(crate) fn build_graph(
+    rm: &mut ReportManager,
+    cc: &CompileContext,
+    pe: &mut ParsedExpression,
+) -> Result<BuiltExpression, CompileError> {
+    let mut builder = make_nfa_builder(pe, cc);
 
     let mut bs = make_glushkov_build_state(&mut builder);
 
     // Map position IDs to characters/components.
     pe.component.note_positions(&mut bs);
 
-    builder.get_graph()
+    if bs.get_builder().is_too_large() {
+        return Err(CompileError::new(
+            ErrorKind::ResourceLimit,
+            "Pattern is too large.",
+        ));
+    }
+
+    // Wire the initial states to the firsts and build the rest of the follow
+    // set.
+    let initials = bs.initials();
+    pe.component.build_follow_set(&mut bs, &initials);
+
+    // Wire the lasts to the accept state.
+    let mut lasts = pe.component.last();
+    replace_epsilons(&mut lasts, &initials);
+    bs.connect_accepts(&lasts);
+
+    // Create our edges.
+    bs.build_edges();
+
+    builder.get_graph(rm)
 }
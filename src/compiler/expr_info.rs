@@ -0,0 +1,24 @@
+use std::collections::BTreeMap;
+
+/// Information about a regular expression, as returned by
+/// [`expression_info`][`crate::expression_info`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ExprInfo {
+    pub(crate) capture_names: BTreeMap<String, u32>,
+}
+
+impl ExprInfo {
+    /// Returns the index of the capturing group with the given name, if any.
+    #[must_use]
+    pub fn capture_index(&self, name: &str) -> Option<u32> {
+        self.capture_names.get(name).copied()
+    }
+
+    /// Iterates over the named capturing groups and their indices, in name
+    /// order.
+    pub fn capture_names(&self) -> impl Iterator<Item = (&str, u32)> + '_ {
+        self.capture_names
+            .iter()
+            .map(|(name, &index)| (name.as_str(), index))
+    }
+}
@@ -16,14 +16,13 @@ pub(crate) struct ExpressionInfo {
     pub(crate) report: ReportId,
 
     /// Vacuous pattern is allowed. (HS_FLAG_ALLOWEMPTY)
-    #[allow(dead_code)]
     pub(super) allow_vacuous: bool,
 
     /// "Highlander" (single match) pattern. (HS_FLAG_SINGLEMATCH)
     pub(crate) highlander: bool,
 
     /// UTF-8 pattern. (`HS_FLAG_UTF8`)
-    pub(super) utf8: bool,
+    pub(crate) utf8: bool,
 
     /// Prefiltering pattern. (`HS_FLAG_PREFILTER`)
     pub(super) prefilter: bool,
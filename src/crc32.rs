@@ -0,0 +1,28 @@
+//! CRC-32C (Castagnoli) checksum.
+
+const POLY: u32 = 0x82f6_3b78; // reflected 0x1EDC6F41
+
+/// Computes the CRC-32C of `data`, continuing from `crc`.
+pub(crate) fn crc32c(crc: u32, data: &[u8]) -> u32 {
+    let mut crc = !crc;
+    for &b in data {
+        crc ^= u32::from(b);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (POLY & mask);
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::crc32c;
+
+    #[test]
+    fn check_value() {
+        assert_eq!(crc32c(0, b""), 0);
+        assert_eq!(crc32c(0, b"123456789"), 0xe306_9283);
+        assert_eq!(crc32c(crc32c(0, b"12345"), b"6789"), 0xe306_9283);
+    }
+}
@@ -1,15 +1,288 @@
-use crate::rose::RoseEngine;
+use crate::crc32::crc32c;
+use crate::rose::{RoseEngine, RoseRuntimeImpl};
+use crate::runtime::{scan, Error, Match, Matching, Stream};
+use crate::scratch::Scratch;
+use crate::util::serialize::{Reader, Writer};
+use crate::Mode;
+use std::sync::Mutex;
+
+/// Marker at the start of every serialized database.
+const MAGIC: &[u8; 8] = b"RESCANDB";
+
+/// Version of the library writing serialized databases; databases are only
+/// loaded by the exact version that wrote them.
+const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 pub struct Database {
     rose: RoseEngine,
+
+    /// Scratch space kept for [`Database::matches`].
+    scratch: Mutex<Option<Scratch>>,
 }
 
 impl Database {
     pub(crate) fn new(rose: RoseEngine) -> Self {
-        Self { rose }
+        Self {
+            rose,
+            scratch: Mutex::new(None),
+        }
+    }
+
+    /// Scans `data` and returns all matches, in the order they are reported.
+    ///
+    /// The scratch space is allocated by the first call and kept with the
+    /// database for the next ones. A call made while another is scanning
+    /// allocates its own.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database cannot be used for block scanning.
+    pub fn matches(&self, data: &[u8]) -> Result<Vec<Match>, Error> {
+        let cached = self.scratch.lock().ok().and_then(|mut s| s.take());
+        let mut scratch = cached.unwrap_or_else(|| Scratch::new(self));
+        let mut matches = Vec::new();
+        let res = scan(self, data, &mut scratch, |id, from, to| {
+            matches.push(Match { id, from, to });
+            Matching::Continue
+        });
+        if let Ok(mut s) = self.scratch.lock() {
+            *s = Some(scratch);
+        }
+        res?;
+        Ok(matches)
+    }
+
+    /// Opens a stream for scanning data written in several chunks.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Invalid`] if the database was not compiled for
+    /// streaming mode.
+    pub fn open_stream(&self) -> Result<Stream<'_>, Error> {
+        if !matches!(self.rose.mode, Mode::Stream(_)) {
+            return Err(Error::Invalid);
+        }
+        Ok(Stream::new(self))
+    }
+
+    /// Returns the mode the database was compiled for.
+    #[must_use]
+    pub fn mode(&self) -> Mode {
+        self.rose.mode
+    }
+
+    /// Returns the strategy picked for scanning with the database.
+    #[must_use]
+    pub fn runtime_impl(&self) -> RoseRuntimeImpl {
+        self.rose.runtime_impl
+    }
+
+    /// Returns the size of the database bytecode in bytes.
+    #[must_use]
+    pub fn bytecode_size(&self) -> usize {
+        self.rose.size()
+    }
+
+    /// Returns the size in bytes of the state of a stream opened on the
+    /// database.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Invalid`] if the database was not compiled for
+    /// streaming mode.
+    pub fn stream_size(&self) -> Result<usize, Error> {
+        Ok(self.open_stream()?.size())
+    }
+
+    /// Returns the length of the shortest data that can produce a match, or
+    /// `u32::MAX` if no data can.
+    #[must_use]
+    pub fn min_width(&self) -> u32 {
+        self.rose.min_width
+    }
+
+    /// Serializes the database into a byte buffer that can be stored or
+    /// sent elsewhere and loaded back with [`Database::deserialize`].
+    ///
+    /// The buffer starts with a header holding the library version and a
+    /// checksum of the bytecode.
+    #[must_use]
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut payload = Writer::default();
+        self.rose.serialize(&mut payload);
+        let payload = payload.into_bytes();
+
+        let mut w = Writer::default();
+        w.put_bytes(MAGIC);
+        w.put_len(VERSION.len());
+        w.put_bytes(VERSION.as_bytes());
+        w.put_len(payload.len());
+        w.put_u32(crc32c(0, &payload));
+        w.put_bytes(&payload);
+        w.into_bytes()
+    }
+
+    /// Reconstructs a database from bytes produced by
+    /// [`Database::serialize`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::DbVersion`] if the bytes were written by another
+    /// version of this library, or [`Error::DbCorrupt`] if they are not a
+    /// valid serialized database.
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, Error> {
+        let mut r = Reader::new(bytes);
+        let corrupt = |_| Error::DbCorrupt;
+
+        if r.bytes(MAGIC.len()).map_err(corrupt)? != MAGIC {
+            return Err(Error::DbCorrupt);
+        }
+        let version_len = r.len(1).map_err(corrupt)?;
+        if r.bytes(version_len).map_err(corrupt)? != VERSION.as_bytes() {
+            return Err(Error::DbVersion);
+        }
+        let payload_len = r.len(1).map_err(corrupt)?;
+        let crc = r.u32().map_err(corrupt)?;
+        let payload = r.bytes(payload_len).map_err(corrupt)?;
+        if !r.is_empty() || crc32c(0, payload) != crc {
+            return Err(Error::DbCorrupt);
+        }
+
+        let mut r = Reader::new(payload);
+        let rose = RoseEngine::deserialize(&mut r).map_err(corrupt)?;
+        if !r.is_empty() {
+            return Err(Error::DbCorrupt);
+        }
+        Ok(Self::new(rose))
     }
 }
 
 pub(crate) fn get_bytecode(db: &Database) -> &RoseEngine {
     &db.rose
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Database, RoseEngine, MAGIC};
+    use crate::{compile_multi, Error, Flags, Match, Mode, RoseRuntimeImpl};
+
+    #[test]
+    fn matches() {
+        let db = compile_multi(
+            &["foo", "bar"],
+            &[Flags::empty(), Flags::CASELESS],
+            &[10, 20],
+            &Mode::Block,
+        )
+        .expect("valid");
+        assert_eq!(
+            db.matches(b"foo BAR foo").expect("scan"),
+            vec![
+                Match {
+                    id: 10,
+                    from: 0,
+                    to: 3
+                },
+                Match {
+                    id: 20,
+                    from: 0,
+                    to: 7
+                },
+                Match {
+                    id: 10,
+                    from: 0,
+                    to: 11
+                },
+            ]
+        );
+        assert!(db.matches(b"fo ba").expect("scan").is_empty());
+
+        // The scratch space is kept for the next call.
+        assert!(db.scratch.lock().expect("not poisoned").is_some());
+    }
+
+    #[test]
+    fn serialize_round_trip() {
+        let db = compile_multi(
+            &["foo", "bar"],
+            &[Flags::empty(), Flags::CASELESS],
+            &[10, 20],
+            &Mode::Block,
+        )
+        .expect("valid");
+        let bytes = db.serialize();
+        let loaded = Database::deserialize(&bytes).expect("deserialize");
+        let data = b"foo BAR fOo bar";
+        assert_eq!(loaded.matches(data), db.matches(data));
+        assert_eq!(loaded.serialize(), bytes);
+    }
+
+    #[test]
+    fn deserialize_version_mismatch() {
+        let db = compile_multi(&["foo"], &[Flags::empty()], &[0], &Mode::Block).expect("valid");
+        let mut bytes = db.serialize();
+        // The version string directly follows the magic and its length.
+        bytes[MAGIC.len() + 4] ^= 0xff;
+        assert_eq!(Database::deserialize(&bytes).err(), Some(Error::DbVersion));
+    }
+
+    #[test]
+    fn deserialize_corrupt() {
+        let db = compile_multi(&["foo"], &[Flags::empty()], &[0], &Mode::Block).expect("valid");
+        let bytes = db.serialize();
+
+        let mut flipped = bytes.clone();
+        *flipped.last_mut().expect("non-empty") ^= 1;
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        for bad in &[
+            &b""[..],
+            b"not a database",
+            &bytes[..bytes.len() - 1],
+            &flipped,
+            &trailing,
+        ] {
+            assert_eq!(Database::deserialize(bad).err(), Some(Error::DbCorrupt));
+        }
+    }
+
+    #[test]
+    fn deserialize_out_of_range() {
+        let corrupt: &[fn(&mut RoseEngine)] = &[
+            |rose| rose.num_ekeys = u32::MAX,
+            |rose| rose.num_lkeys = u32::MAX,
+            |rose| rose.reports[0].offset_adjust = i32::MIN,
+        ];
+        for f in corrupt {
+            let mut db =
+                compile_multi(&["foo"], &[Flags::empty()], &[0], &Mode::Block).expect("valid");
+            f(&mut db.rose);
+            // Serializing computes a valid checksum over the corrupt engine.
+            let bytes = db.serialize();
+            assert_eq!(Database::deserialize(&bytes).err(), Some(Error::DbCorrupt));
+        }
+    }
+
+    #[test]
+    fn info() {
+        let db = compile_multi(
+            &["foobar", "ab|c"],
+            &[Flags::empty(); 2],
+            &[0, 1],
+            &Mode::Block,
+        )
+        .expect("valid");
+        assert_eq!(db.mode(), Mode::Block);
+        assert_eq!(db.runtime_impl(), RoseRuntimeImpl::FullRose);
+        assert_eq!(db.min_width(), 1);
+        assert!(db.bytecode_size() > 0);
+        assert_eq!(db.stream_size(), Err(Error::Invalid));
+
+        let db = compile_multi(&["foobar"], &[Flags::empty()], &[0], &Mode::Stream(None))
+            .expect("valid");
+        assert_eq!(db.mode(), Mode::Stream(None));
+        assert_eq!(db.runtime_impl(), RoseRuntimeImpl::PureLiteral);
+        assert_eq!(db.min_width(), 6);
+        assert!(db.stream_size().expect("streaming") > 0);
+    }
+}
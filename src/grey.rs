@@ -3,6 +3,7 @@
 pub(crate) struct Grey {
     pub(crate) allow_literal: bool,
 
+    #[allow(dead_code)]
     pub(crate) flood_as_puffette: bool,
 
     pub(crate) shortcut_literals: bool,
@@ -16,6 +17,7 @@ pub(crate) struct Grey {
     pub(crate) limit_pattern_count: usize,
     pub(crate) limit_pattern_length: usize,
     pub(crate) limit_report_count: usize,
+    pub(crate) limit_graph_vertex_count: usize,
 }
 
 impl Default for Grey {
@@ -31,6 +33,7 @@ impl Default for Grey {
             limit_pattern_count: 8_000_000,
             limit_pattern_length: 16_000,
             limit_report_count: 4 * 8_000_000,
+            limit_graph_vertex_count: 500_000,
         }
     }
 }
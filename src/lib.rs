@@ -1,6 +1,8 @@
 mod compiler;
+mod crc32;
 mod database;
 mod grey;
+mod nfa;
 mod nfagraph;
 mod parser;
 mod rose;
@@ -11,12 +13,14 @@ mod som;
 mod util;
 
 use bitflags::bitflags;
+pub use compiler::ExprInfo;
 use compiler::{add_expression, build};
 pub use database::Database;
 use grey::Grey;
 use itertools::izip;
 use nfagraph::Ng;
-pub use runtime::{scan, Error};
+pub use rose::RoseRuntimeImpl;
+pub use runtime::{close_stream, scan, scan_vectored, Error, Match, Matching, Stream};
 pub use scratch::Scratch;
 use std::convert::TryInto;
 pub use util::compile_error::{CompileError, ErrorKind};
@@ -32,6 +36,7 @@ pub(crate) use som::SomType;
 ///
 /// [`compile`]: fn.compile.html
 /// [`compile_multi`]: fn.compile_multi.html
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Mode {
     /// Block scan database.
     Block,
@@ -64,6 +69,7 @@ impl Mode {
     }
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum SomHorizon {
     Large,
     Medium,
@@ -79,6 +85,16 @@ pub fn compile(expression: &str, flags: Flags, mode: &Mode) -> Result<Database,
     compile_multi_int(&[expression], &[flags], &[0], mode, &Grey::default())
 }
 
+/// Parses a regular expression without compiling it, and returns
+/// information about it such as the indices of its named capturing groups.
+///
+/// # Errors
+///
+/// Returns an error if the expression is invalid.
+pub fn expression_info(expression: &str, flags: Flags) -> Result<ExprInfo, CompileError> {
+    compiler::expression_info(expression, flags)
+}
+
 /// Compiles multiple regular expressions.
 ///
 /// # Errors
@@ -128,6 +144,13 @@ fn compile_multi_int(
         }
     }
 
+    // Check that combinations refer to the IDs of other expressions.
+    let combination: Vec<bool> = flags
+        .iter()
+        .map(|fl| fl.contains(Flags::COMBINATION))
+        .collect();
+    ng.rm.pl.validate_sub_ids(ids, &combination)?;
+
     Ok(build(&ng))
 }
 
@@ -160,4 +183,109 @@ mod tests {
     fn compile_multi_int_empty_input() {
         assert!(super::compile_multi_int(&[], &[], &[], &Mode::Block, &Grey::default()).is_err());
     }
+
+    #[test]
+    fn compile_possessive_unsupported() {
+        let e = super::compile("a*+", super::Flags::empty(), &Mode::Block)
+            .err()
+            .expect("possessive quantifiers are rejected");
+        assert!(matches!(e.kind(), super::ErrorKind::Parse));
+    }
+
+    #[test]
+    fn compile_too_large() {
+        for pattern in &["((a{100}){100}){100}", "(((a{100}){100}){100}){100}"] {
+            let e = super::compile(pattern, super::Flags::empty(), &Mode::Block)
+                .err()
+                .expect("too many vertices");
+            assert!(matches!(e.kind(), super::ErrorKind::ResourceLimit));
+            assert_eq!(e.to_string(), "Pattern is too large. in expression 0");
+        }
+        assert!(super::compile("(a{100}){100}", super::Flags::empty(), &Mode::Block).is_ok());
+    }
+
+    #[test]
+    fn compile_combination_errors() {
+        use super::Flags;
+        let error = |patterns: &[&str], flags: &[Flags], ids: &[u32]| {
+            super::compile_multi(patterns, flags, ids, &Mode::Block)
+                .err()
+                .expect("invalid combination")
+                .to_string()
+        };
+        assert_eq!(
+            error(
+                &["a", "1 & 3"],
+                &[Flags::empty(), Flags::COMBINATION],
+                &[1, 2]
+            ),
+            "Unknown sub-expression id 3. in expression 1"
+        );
+        assert_eq!(
+            error(
+                &["a", "1", "2"],
+                &[Flags::empty(), Flags::COMBINATION, Flags::COMBINATION],
+                &[1, 2, 3]
+            ),
+            "No logical combination of logical combination. in expression 2"
+        );
+        assert_eq!(
+            error(&["a", "(1"], &[Flags::empty(), Flags::COMBINATION], &[1, 2]),
+            "Not enough right parentheses at index 2 in expression 1"
+        );
+        assert_eq!(
+            error(
+                &["a", "1"],
+                &[Flags::empty(), Flags::COMBINATION | Flags::CASELESS],
+                &[1, 2]
+            ),
+            "Only QUIET and SINGLEMATCH are supported in combination with COMBINATION. in expression 1"
+        );
+    }
+
+    #[test]
+    fn compile_vacuous() {
+        use super::Flags;
+        for pattern in &["a*", "()", "a?|b"] {
+            let e = super::compile(pattern, Flags::empty(), &Mode::Block)
+                .err()
+                .expect("vacuous patterns need ALLOWEMPTY");
+            assert_eq!(
+                e.to_string(),
+                "Pattern matches empty buffer; use ALLOWEMPTY to enable support. in expression 0"
+            );
+            assert!(super::compile(pattern, Flags::ALLOWEMPTY, &Mode::Block).is_ok());
+        }
+
+        // Only those matching at every offset are rejected.
+        for pattern in &["^", "$", "^$", "^a*"] {
+            assert!(super::compile(pattern, Flags::empty(), &Mode::Block).is_ok());
+        }
+    }
+
+    #[test]
+    fn expression_info_capture_names() {
+        let info = super::expression_info(
+            "(a)(?<first>b)(?:c)(?P<second>d)(?'third'e)",
+            super::Flags::empty(),
+        )
+        .expect("valid");
+        assert_eq!(info.capture_index("first"), Some(2));
+        assert_eq!(info.capture_index("second"), Some(3));
+        assert_eq!(info.capture_index("third"), Some(4));
+        assert_eq!(info.capture_index("fourth"), None);
+        assert_eq!(
+            info.capture_names().collect::<Vec<_>>(),
+            vec![("first", 2), ("second", 3), ("third", 4)]
+        );
+    }
+
+    #[test]
+    fn expression_info_prefilter() {
+        assert!(super::expression_info("(a)\\1", super::Flags::empty()).is_err());
+        assert!(super::expression_info("(a)\\1", super::Flags::PREFILTER).is_ok());
+
+        let info = super::expression_info("(?<x>a)\\1", super::Flags::PREFILTER).expect("valid");
+        assert_eq!(info.capture_index("x"), Some(1));
+    }
 }
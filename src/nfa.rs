@@ -0,0 +1,5 @@
+mod nfa_api;
+mod nfa_internal;
+
+pub(crate) use nfa_api::{nfa_exec, nfa_exec_eod, StateSet};
+pub(crate) use nfa_internal::{Nfa, NfaStateInfo};
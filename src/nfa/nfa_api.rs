@@ -0,0 +1,148 @@
+use super::Nfa;
+use crate::runtime::Matching;
+use crate::util::ReportId;
+use bit_vec::BitVec;
+
+/// Set of NFA states that are on.
+#[derive(Default)]
+pub(crate) struct StateSet {
+    list: Vec<u32>,
+    mask: BitVec,
+}
+
+impl Clone for StateSet {
+    /// Clones the set, keeping its capacity so that the clone is as ready to
+    /// use as the original.
+    fn clone(&self) -> Self {
+        let mut list = Vec::with_capacity(self.list.capacity());
+        list.extend_from_slice(&self.list);
+        Self {
+            list,
+            mask: self.mask.clone(),
+        }
+    }
+}
+
+impl StateSet {
+    /// Creates an empty set able to hold the states of an automaton of
+    /// `num_states` states.
+    pub(crate) fn new(num_states: usize) -> Self {
+        Self {
+            list: Vec::with_capacity(num_states),
+            mask: BitVec::from_elem(num_states, false),
+        }
+    }
+
+    /// Makes sure the set can hold `num_states` states.
+    pub(crate) fn reserve(&mut self, num_states: usize) {
+        if self.mask.len() < num_states {
+            self.mask.grow(num_states - self.mask.len(), false);
+            self.list.reserve(num_states - self.list.len());
+        }
+    }
+
+    /// Returns the number of bytes allocated on the heap for the set.
+    pub(crate) fn heap_size(&self) -> usize {
+        self.list.capacity() * std::mem::size_of::<u32>() + self.mask.capacity() / 8
+    }
+
+    pub(crate) fn clear(&mut self) {
+        for &s in &self.list {
+            self.mask.set(s as usize, false);
+        }
+        self.list.clear();
+    }
+
+    pub(crate) fn states(&self) -> &[u32] {
+        &self.list
+    }
+
+    pub(crate) fn insert(&mut self, s: u32) {
+        if !self.mask[s as usize] {
+            self.mask.set(s as usize, true);
+            self.list.push(s);
+        }
+    }
+}
+
+/// Collects the reports of the states in `on` into `reports`, sorted and
+/// without duplicates.
+fn collect_reports(nfa: &Nfa, on: &StateSet, reports: &mut Vec<ReportId>, eod: bool) {
+    reports.clear();
+    for &s in on.states() {
+        let info = &nfa.states[s as usize];
+        if eod {
+            reports.extend(&info.reports_eod);
+        } else {
+            reports.extend(&info.reports);
+        }
+    }
+    reports.sort_unstable();
+    reports.dedup();
+}
+
+/// Runs the NFA over `data`, whose first byte is at stream offset `offset`.
+///
+/// `cur` holds the states that are on before the first byte and is updated to
+/// those on after the last one; `next` is scratch space. `on_match` is called
+/// with the reports raised at each end offset.
+pub(crate) fn nfa_exec<F>(
+    nfa: &Nfa,
+    cur: &mut StateSet,
+    next: &mut StateSet,
+    reports: &mut Vec<ReportId>,
+    data: &[u8],
+    offset: u64,
+    mut on_match: F,
+) -> Matching
+where
+    F: FnMut(&[ReportId], u64) -> Matching,
+{
+    for (i, &c) in data.iter().enumerate() {
+        let loc = offset + i as u64;
+        next.clear();
+        for &s in cur.states() {
+            for &t in &nfa.states[s as usize].succ {
+                if nfa.states[t as usize].reach.test(c) {
+                    next.insert(t);
+                }
+            }
+        }
+        if loc == 0 {
+            for &t in &nfa.init[c as usize] {
+                next.insert(t);
+            }
+        }
+        for &t in &nfa.init_ds[c as usize] {
+            next.insert(t);
+        }
+        std::mem::swap(cur, next);
+
+        collect_reports(nfa, cur, reports, false);
+        if !reports.is_empty() {
+            if let Matching::Terminate = on_match(reports, loc + 1) {
+                return Matching::Terminate;
+            }
+        }
+    }
+    Matching::Continue
+}
+
+/// Raises the end-of-data reports of the states in `cur`, at stream offset
+/// `offset`.
+pub(crate) fn nfa_exec_eod<F>(
+    nfa: &Nfa,
+    cur: &StateSet,
+    reports: &mut Vec<ReportId>,
+    offset: u64,
+    mut on_match: F,
+) -> Matching
+where
+    F: FnMut(&[ReportId], u64) -> Matching,
+{
+    collect_reports(nfa, cur, reports, true);
+    if reports.is_empty() {
+        return Matching::Continue;
+    }
+    on_match(reports, offset)
+}
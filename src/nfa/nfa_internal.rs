@@ -0,0 +1,133 @@
+use crate::util::serialize::{CorruptError, Reader, Writer};
+use crate::util::{fill_bitvector, from_bitvector, CharReach, ReportId};
+use std::mem;
+
+/// A single state of an [`Nfa`].
+pub(crate) struct NfaStateInfo {
+    /// Set of characters on which this state is entered.
+    pub(crate) reach: CharReach,
+
+    /// States that may be entered after this one.
+    pub(crate) succ: Vec<u32>,
+
+    /// Reports raised whenever this state is entered.
+    pub(crate) reports: Vec<ReportId>,
+
+    /// Reports raised if this state is on at the end of data.
+    pub(crate) reports_eod: Vec<ReportId>,
+}
+
+/// A position automaton run by keeping track of the set of states that are
+/// on.
+///
+/// Each state is entered by consuming a single byte in its reachability, so
+/// transitions carry no labels of their own.
+pub(crate) struct Nfa {
+    pub(crate) states: Vec<NfaStateInfo>,
+
+    /// States entered from the anchored start, indexed by the first byte of
+    /// the data.
+    pub(crate) init: Vec<Vec<u32>>,
+
+    /// States entered from the floating start, indexed by the byte consumed.
+    pub(crate) init_ds: Vec<Vec<u32>>,
+}
+
+impl Default for Nfa {
+    fn default() -> Self {
+        Self {
+            states: Vec::new(),
+            init: vec![Vec::new(); 256],
+            init_ds: vec![Vec::new(); 256],
+        }
+    }
+}
+
+impl Nfa {
+    /// Number of states in the automaton.
+    pub(crate) fn num_states(&self) -> usize {
+        self.states.len()
+    }
+
+    /// Returns the number of bytes allocated on the heap for the automaton.
+    pub(crate) fn heap_size(&self) -> usize {
+        let vec_size = |v: &Vec<u32>| v.capacity() * mem::size_of::<u32>();
+        let states: usize = self
+            .states
+            .iter()
+            .map(|s| vec_size(&s.succ) + vec_size(&s.reports) + vec_size(&s.reports_eod))
+            .sum();
+        let tables: usize = self
+            .init
+            .iter()
+            .chain(&self.init_ds)
+            .map(|v| mem::size_of::<Vec<u32>>() + vec_size(v))
+            .sum();
+        self.states.capacity() * mem::size_of::<NfaStateInfo>() + states + tables
+    }
+
+    pub(crate) fn serialize(&self, w: &mut Writer) {
+        w.put_len(self.states.len());
+        for s in &self.states {
+            let mut bits = [0; 32];
+            fill_bitvector(&s.reach, &mut bits);
+            w.put_bytes(&bits);
+            w.put_u32_slice(&s.succ);
+            w.put_u32_slice(&s.reports);
+            w.put_u32_slice(&s.reports_eod);
+        }
+        for table in &[&self.init, &self.init_ds] {
+            for states in table.iter() {
+                w.put_u32_slice(states);
+            }
+        }
+    }
+
+    /// Reads an automaton raising reports below `num_reports`.
+    pub(crate) fn deserialize(r: &mut Reader, num_reports: usize) -> Result<Self, CorruptError> {
+        let num_states = r.len(32 + 3 * 4)?;
+        let mut states = Vec::with_capacity(num_states);
+        for _ in 0..num_states {
+            let reach = from_bitvector(r.bytes(32)?);
+            let succ = r.u32_vec()?;
+            let reports = r.u32_vec()?;
+            let reports_eod = r.u32_vec()?;
+            check_ids(&succ, num_states)?;
+            check_ids(&reports, num_reports)?;
+            check_ids(&reports_eod, num_reports)?;
+            states.push(NfaStateInfo {
+                reach,
+                succ,
+                reports,
+                reports_eod,
+            });
+        }
+
+        let mut read_table = || -> Result<Vec<Vec<u32>>, CorruptError> {
+            (0..256)
+                .map(|_| {
+                    let ids = r.u32_vec()?;
+                    check_ids(&ids, num_states)?;
+                    Ok(ids)
+                })
+                .collect()
+        };
+        let init = read_table()?;
+        let init_ds = read_table()?;
+
+        Ok(Self {
+            states,
+            init,
+            init_ds,
+        })
+    }
+}
+
+/// Checks that all `ids` are below `bound`.
+fn check_ids(ids: &[u32], bound: usize) -> Result<(), CorruptError> {
+    if ids.iter().all(|&id| (id as usize) < bound) {
+        Ok(())
+    } else {
+        Err(CorruptError)
+    }
+}
@@ -1,7 +1,15 @@
 mod ng;
+mod ng_asserts;
 mod ng_builder;
 mod ng_holder;
+mod ng_limex;
+mod ng_vacuous;
+mod ng_width;
 
 pub(crate) use ng::Ng;
+pub(crate) use ng_asserts::resolve_asserts;
 pub(crate) use ng_builder::{make_nfa_builder, NfaBuilder};
-pub(crate) use ng_holder::{NfaVertex, NgHolder};
+pub(crate) use ng_holder::{make_literal_graph, NfaVertex, NgHolder};
+pub(crate) use ng_limex::construct_nfa;
+pub(crate) use ng_vacuous::remove_vacuous;
+pub(crate) use ng_width::find_min_width;
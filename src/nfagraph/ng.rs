@@ -1,5 +1,5 @@
 use crate::compiler::ExpressionInfo;
-use crate::nfagraph::NgHolder;
+use crate::nfagraph::{find_min_width, remove_vacuous, resolve_asserts, NgHolder};
 use crate::rose::RoseBuild;
 use crate::util::{
     make_e_callback, make_som_relative_callback, BoundaryReports, CompileContext, Depth,
@@ -18,10 +18,9 @@ pub(crate) struct Ng<'a> {
     /// The length of the shortest corpus which can match a pattern contained in
     /// the `Ng` (excluding the boundary reports used by vacuous patterns, which
     /// give an effective `min_width` of zero).
-    min_width: Depth,
+    pub(crate) min_width: Depth,
 
-    rm: ReportManager<'a>,
-    #[allow(dead_code)]
+    pub(crate) rm: ReportManager<'a>,
     boundary: BoundaryReports,
     pub(crate) cc: &'a CompileContext,
 
@@ -40,28 +39,50 @@ impl<'a> Ng<'a> {
             min_width: Depth::infinity(),
             rm: ReportManager::new(&cc.grey),
             boundary: BoundaryReports::default(),
-            cc: &cc,
+            cc,
             smwr: SmallWriteBuild::new(num_patterns, cc),
-            rose: RoseBuild {
-                cc: &cc,
-                has_som: false,
-            },
+            rose: RoseBuild::new(cc),
             _pin: PhantomPinned,
         };
         let mut boxed = Box::pin(res);
 
         let rm = NonNull::from(&boxed.rm);
+        let boundary = NonNull::from(&boxed.boundary);
         unsafe {
             let mut_ref: Pin<&mut Self> = Pin::as_mut(&mut boxed);
-            Pin::get_unchecked_mut(mut_ref).smwr.rm = rm;
+            let inner = Pin::get_unchecked_mut(mut_ref);
+            inner.smwr.rm = rm;
+            inner.rose.rm = rm;
+            inner.rose.boundary = boundary;
         }
         boxed
     }
 
-    pub(crate) fn add_graph(&mut self, expr: &ExpressionInfo, _g: &NgHolder) {
+    pub(crate) fn add_graph(
+        &mut self,
+        expr: &ExpressionInfo,
+        mut g: NgHolder,
+    ) -> Result<(), CompileError> {
         if expr.som != SomType::None {
             self.rose.has_som = true;
         }
+
+        resolve_asserts(&mut self.rm, &mut g, expr)?;
+
+        if g.is_vacuous() {
+            if expr.som != SomType::None {
+                return Err(CompileError::new(
+                    ErrorKind::Other,
+                    "Start of match is not currently supported for patterns which match an empty buffer.",
+                ));
+            }
+            remove_vacuous(&mut g, &mut self.boundary);
+        }
+
+        self.min_width = min(self.min_width, find_min_width(&g));
+
+        self.rose.add_outfix(g);
+        Ok(())
     }
 
     pub(crate) fn add_literal(
@@ -0,0 +1,379 @@
+//! Resolution of word boundary assertions.
+//!
+//! The Glushkov construction leaves `\b` and `\B` as assertion flags on the
+//! edges crossing them. Here we split the vertices on either side into their
+//! word and non-word characters, so that each assertion can be decided by the
+//! edge alone, and add the vertices needed to look at the character before
+//! the start of a match and after its end.
+//!
+//! With UCP in UTF-8 mode, the characters on either side are whole code
+//! points: UTF-8 classes give word and non-word characters positions of their
+//! own, and the characters looked at are matched by automata over their
+//! encodings.
+
+use crate::compiler::ExpressionInfo;
+use crate::nfagraph::{NfaVertex, NgHolder};
+use crate::parser::{ByteSequences, PosFlags, Utf8Dfa};
+use crate::util::{ucp_word_chars, utf8_sequences, CharReach, ReportId, ReportManager};
+use crate::CompileError;
+use std::collections::{HashMap, HashSet};
+
+/// Whether a character is a word character.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum Side {
+    Word,
+    NonWord,
+}
+
+/// Returns the bytes of ASCII word characters.
+///
+/// These are all the word characters, except with UCP in UTF-8 mode.
+fn word_reach() -> CharReach {
+    let mut cr = CharReach::from_range(b'a', b'z');
+    cr.set_range(b'A', b'Z');
+    cr.set_range(b'0', b'9');
+    cr.set(b'_');
+    cr
+}
+
+fn side_reach(side: Side, word: CharReach) -> CharReach {
+    match side {
+        Side::Word => word,
+        Side::NonWord => !word,
+    }
+}
+
+/// Returns `true` if the assertions in `flags` hold between a character on
+/// side `from` and one on side `to`.
+fn allows(flags: PosFlags, from: Side, to: Side) -> bool {
+    let needed = match (from, to) {
+        (Side::Word, Side::NonWord) => {
+            PosFlags::ASSERT_WORD_TO_NONWORD | PosFlags::ASSERT_WORD_TO_NONWORD_UCP
+        }
+        (Side::NonWord, Side::Word) => {
+            PosFlags::ASSERT_NONWORD_TO_WORD | PosFlags::ASSERT_NONWORD_TO_WORD_UCP
+        }
+        (Side::Word, Side::Word) => {
+            PosFlags::ASSERT_WORD_TO_WORD | PosFlags::ASSERT_WORD_TO_WORD_UCP
+        }
+        (Side::NonWord, Side::NonWord) => {
+            PosFlags::ASSERT_NONWORD_TO_NONWORD | PosFlags::ASSERT_NONWORD_TO_NONWORD_UCP
+        }
+    };
+    flags.intersects(needed)
+}
+
+/// Returns the encodings of the characters on side `side`, each as a range
+/// per byte: those of whole code points with UCP in UTF-8 mode, single bytes
+/// otherwise.
+fn side_sequences(side: Side, utf8_ucp: bool) -> ByteSequences {
+    if utf8_ucp {
+        let mut cps = ucp_word_chars();
+        if side == Side::NonWord {
+            cps.flip();
+        }
+        cps.ranges()
+            .iter()
+            .flat_map(|&(lo, hi)| utf8_sequences(lo, hi))
+            .collect()
+    } else {
+        let cr = side_reach(side, word_reach());
+        (0..=255)
+            .filter(|&b| cr.test(b))
+            .map(|b| vec![(b, b)])
+            .collect()
+    }
+}
+
+/// Adds vertices matching the byte sequences in `seqs`, returning those
+/// matching their first byte and those matching their last.
+fn add_sequences(g: &mut NgHolder, seqs: ByteSequences) -> (Vec<NfaVertex>, Vec<NfaVertex>) {
+    let mut dfa = Utf8Dfa::new();
+    let initial = dfa.build(seqs);
+
+    // A vertex per transition of the automaton, shared by the transitions on
+    // the same bytes to the same state.
+    let mut vertices: HashMap<(CharReach, usize), NfaVertex> = HashMap::new();
+    let mut order = Vec::new();
+    let mut seen = HashSet::new();
+    let mut stack = vec![initial];
+    while let Some(state) = stack.pop() {
+        for &(cr, next) in &dfa.states[state] {
+            vertices.entry((cr, next)).or_insert_with(|| {
+                let v = g.add_vertex();
+                g[v].char_reach = cr;
+                order.push((next, v));
+                v
+            });
+            if next != Utf8Dfa::ACCEPT && seen.insert(next) {
+                stack.push(next);
+            }
+        }
+    }
+    for &(next, v) in &order {
+        for t in &dfa.states[next] {
+            g.add_edge(v, vertices[t]);
+        }
+    }
+
+    let first = dfa.states[initial].iter().map(|t| vertices[t]).collect();
+    let last = order
+        .iter()
+        .filter(|&&(next, _)| next == Utf8Dfa::ACCEPT)
+        .map(|&(_, v)| v)
+        .collect();
+    (first, last)
+}
+
+/// Adds vertices matching a character on side `to` after the end of a
+/// match, raising `reports` for the offset before it. Returns the vertices
+/// matching its first byte.
+fn add_after(
+    rm: &mut ReportManager,
+    g: &mut NgHolder,
+    to: Side,
+    reports: &[ReportId],
+    utf8_ucp: bool,
+) -> Result<Vec<NfaVertex>, CompileError> {
+    // The reports are adjusted by the length of the character, so each
+    // length gets vertices of its own.
+    let mut by_len: Vec<ByteSequences> = vec![Vec::new(); 4];
+    for seq in side_sequences(to, utf8_ucp) {
+        by_len[seq.len() - 1].push(seq);
+    }
+
+    let mut entries = Vec::new();
+    for (len, seqs) in (1..).zip(by_len) {
+        if seqs.is_empty() {
+            continue;
+        }
+        let mut adjusted = Vec::with_capacity(reports.len());
+        for &id in reports {
+            let mut ir = rm.get_report(id).clone();
+            ir.offset_adjust -= len;
+            adjusted.push(rm.get_internal_id(&ir)?);
+        }
+        let (first, last) = add_sequences(g, seqs);
+        for v in last {
+            g[v].reports.extend(adjusted.iter().copied());
+            g.add_edge(v, g.accept);
+        }
+        entries.extend(first);
+    }
+    Ok(entries)
+}
+
+/// Splits `v` into a vertex for its word characters and one for its non-word
+/// characters, recording the side of each in `sides`.
+///
+/// Vertices of UTF-8 classes in UCP mode are already on one side, which their
+/// flags tell.
+fn split_vertex(
+    g: &mut NgHolder,
+    v: NfaVertex,
+    word: CharReach,
+    sides: &mut HashMap<NfaVertex, Side>,
+) {
+    if g[v].assert_flags.contains(PosFlags::WORD_CHAR) {
+        sides.insert(v, Side::Word);
+        return;
+    }
+    if g[v].assert_flags.contains(PosFlags::NONWORD_CHAR) {
+        sides.insert(v, Side::NonWord);
+        return;
+    }
+
+    let cr = g[v].char_reach;
+    let word_cr = cr & word;
+    let non_word_cr = cr & !word;
+    if word_cr.none() {
+        sides.insert(v, Side::NonWord);
+        return;
+    }
+    if non_word_cr.none() {
+        sides.insert(v, Side::Word);
+        return;
+    }
+
+    let clone = g.add_vertex();
+    g[clone].char_reach = non_word_cr;
+    g[clone].reports = g[v].reports.clone();
+    g[clone].assert_flags = g[v].assert_flags;
+    g[v].char_reach = word_cr;
+
+    for (s, t, flags) in g.edges() {
+        if s == v && t == v {
+            g.add_edge_with_flags(clone, clone, flags);
+            g.add_edge_with_flags(clone, v, flags);
+            g.add_edge_with_flags(v, clone, flags);
+        } else if s == v {
+            g.add_edge_with_flags(clone, t, flags);
+        } else if t == v {
+            g.add_edge_with_flags(s, clone, flags);
+        }
+    }
+
+    sides.insert(v, Side::Word);
+    sides.insert(clone, Side::NonWord);
+}
+
+/// Rewrites the word boundary assertions of `g` into plain vertices and
+/// edges.
+pub(crate) fn resolve_asserts(
+    rm: &mut ReportManager,
+    g: &mut NgHolder,
+    expr: &ExpressionInfo,
+) -> Result<(), CompileError> {
+    let asserted: Vec<_> = g
+        .edges()
+        .into_iter()
+        .filter(|(_, _, flags)| !flags.is_empty())
+        .collect();
+    if asserted.is_empty() {
+        return Ok(());
+    }
+
+    let utf8_ucp = expr.utf8
+        && asserted
+            .iter()
+            .any(|(_, _, flags)| flags.intersects(PosFlags::UCP_WORD_ASSERTS));
+    let word = word_reach();
+
+    let mut sides = HashMap::new();
+    for &(u, v, _) in &asserted {
+        for x in [u, v].iter().copied() {
+            if !g.is_special(x) && !sides.contains_key(&x) {
+                split_vertex(g, x, word, &mut sides);
+            }
+        }
+    }
+
+    // Vertices matching the last byte of the character before a floating
+    // match, and the first byte of the one after the end of a match, by side
+    // (and reports, for the latter).
+    let mut befores: HashMap<Side, Vec<NfaVertex>> = HashMap::new();
+    let mut afters: HashMap<(Side, Vec<ReportId>), Vec<NfaVertex>> = HashMap::new();
+
+    for (u, v, flags) in g.edges() {
+        if flags.is_empty() {
+            continue;
+        }
+        g.remove_edge(u, v);
+
+        // The vertices the edge can leave from, with the side of the
+        // character before it; `None` stands for the vertex matching the
+        // character before a floating match.
+        let sources = if let Some(&from) = sides.get(&u) {
+            vec![(Some(u), from)]
+        } else if u == g.start {
+            // The start of data counts as a non-word character.
+            vec![(Some(u), Side::NonWord)]
+        } else {
+            vec![
+                (Some(g.start), Side::NonWord),
+                (None, Side::Word),
+                (None, Side::NonWord),
+            ]
+        };
+
+        // The vertices the edge can lead to, with the side of the character
+        // after it; `None` stands for the vertex matching the character after
+        // the end of a match.
+        let targets = if let Some(&to) = sides.get(&v) {
+            vec![(Some(v), to)]
+        } else if v == g.accept {
+            // As does the end of data.
+            vec![
+                (None, Side::Word),
+                (None, Side::NonWord),
+                (Some(g.accept_eod), Side::NonWord),
+            ]
+        } else {
+            vec![(Some(g.accept_eod), Side::NonWord)]
+        };
+
+        let mut reports: Vec<_> = g[u].reports.iter().copied().collect();
+        reports.sort_unstable();
+        for &(src, from) in &sources {
+            for &(dst, to) in &targets {
+                if !allows(flags, from, to) {
+                    continue;
+                }
+                let srcs = match src {
+                    Some(src) => vec![src],
+                    None => befores
+                        .entry(from)
+                        .or_insert_with(|| {
+                            let (first, last) = add_sequences(g, side_sequences(from, utf8_ucp));
+                            for v in first {
+                                g.add_edge(g.start_ds, v);
+                            }
+                            last
+                        })
+                        .clone(),
+                };
+                let dsts = match dst {
+                    Some(dst) => vec![dst],
+                    None => {
+                        let key = (to, reports.clone());
+                        if let Some(after) = afters.get(&key) {
+                            after.clone()
+                        } else {
+                            let after = add_after(rm, g, to, &reports, utf8_ucp)?;
+                            afters.insert(key, after.clone());
+                            after
+                        }
+                    }
+                };
+                for &src in &srcs {
+                    for &dst in &dsts {
+                        if dst == g.accept_eod && src != u {
+                            // A vacuous match at the end of data, seen from
+                            // the start or from the character before it.
+                            g[src].reports.extend(reports.iter().copied());
+                        }
+                        g.add_edge(src, dst);
+                    }
+                }
+            }
+        }
+    }
+
+    prune_unreachable(g);
+    Ok(())
+}
+
+/// Removes the vertices left without predecessors.
+fn prune_unreachable(g: &mut NgHolder) {
+    loop {
+        let dead: Vec<_> = g
+            .vertices()
+            .filter(|&v| !g.is_special(v) && g.predecessors(v).next().is_none())
+            .collect();
+        if dead.is_empty() {
+            return;
+        }
+        g.remove_vertices(dead);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allowed_transitions() {
+        let b = PosFlags::ASSERT_WORD_TO_NONWORD | PosFlags::ASSERT_NONWORD_TO_WORD;
+        assert!(allows(b, Side::Word, Side::NonWord));
+        assert!(allows(b, Side::NonWord, Side::Word));
+        assert!(!allows(b, Side::Word, Side::Word));
+        assert!(!allows(b, Side::NonWord, Side::NonWord));
+
+        let nb = PosFlags::ASSERT_WORD_TO_WORD_UCP | PosFlags::ASSERT_NONWORD_TO_NONWORD_UCP;
+        assert!(allows(nb, Side::Word, Side::Word));
+        assert!(!allows(nb, Side::NonWord, Side::Word));
+
+        assert!(word_reach().test(b'_'));
+        assert!(!word_reach().test(0xe9));
+    }
+}
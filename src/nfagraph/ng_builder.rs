@@ -1,19 +1,29 @@
 use crate::compiler::{BuiltExpression, ExpressionInfo, ParsedExpression};
 use crate::nfagraph::{NfaVertex, NgHolder};
 use crate::parser::{PosFlags, Position};
-use crate::util::CharReach;
+use crate::util::{CharReach, CompileContext, ReportManager};
+use crate::CompileError;
 
 pub(crate) struct NfaBuilder {
     graph: NgHolder,
     expr: ExpressionInfo,
+
+    /// Positions that raise the expression's report, with the offset
+    /// adjustment to apply to it.
+    report_positions: Vec<(Position, i32)>,
+
+    /// Largest number of vertices the graph may have.
+    vertex_limit: usize,
 }
 
 impl NfaBuilder {
-    fn new(parsed: &ParsedExpression) -> Self {
+    fn new(parsed: &ParsedExpression, cc: &CompileContext) -> Self {
         let graph = NgHolder::new();
         Self {
             graph,
             expr: parsed.expr.clone(),
+            report_positions: Vec::new(),
+            vertex_limit: cc.grey.limit_graph_vertex_count,
         }
     }
 
@@ -45,6 +55,24 @@ impl NfaBuilder {
         self.graph[pos].assert_flags
     }
 
+    pub(crate) fn set_assert_flag(&mut self, pos: NfaVertex, flags: PosFlags) {
+        self.graph[pos].assert_flags |= flags;
+    }
+
+    /// Adds an edge from `from` to `to`, subject to the word boundary
+    /// assertions in `assert_flags`.
+    pub(crate) fn add_edge(&mut self, from: NfaVertex, to: NfaVertex, assert_flags: PosFlags) {
+        self.graph.add_edge_with_flags(from, to, assert_flags);
+    }
+
+    /// Marks `pos` as raising the expression's report, `offset_adjust` bytes
+    /// away from the current offset.
+    pub(crate) fn set_node_report_id(&mut self, pos: Position, offset_adjust: i32) {
+        if !self.report_positions.contains(&(pos, offset_adjust)) {
+            self.report_positions.push((pos, offset_adjust));
+        }
+    }
+
     fn add_vertex(&mut self) -> NfaVertex {
         self.graph.add_vertex()
     }
@@ -54,14 +82,30 @@ impl NfaBuilder {
         self.graph.num_vertices()
     }
 
-    pub(crate) fn get_graph(self) -> BuiltExpression {
-        BuiltExpression {
+    /// Returns `true` if the graph has grown past the vertex limit, in which
+    /// case the expression is rejected.
+    pub(crate) fn is_too_large(&self) -> bool {
+        self.graph.num_vertices() > self.vertex_limit
+    }
+
+    /// Finishes the graph, turning the report positions into internal reports
+    /// managed by `rm`.
+    pub(crate) fn get_graph(
+        mut self,
+        rm: &mut ReportManager,
+    ) -> Result<BuiltExpression, CompileError> {
+        for &(pos, offset_adjust) in &self.report_positions {
+            let ir = rm.get_basic_internal_report(&self.expr, offset_adjust)?;
+            let id = rm.get_internal_id(&ir)?;
+            self.graph[pos].reports.insert(id);
+        }
+        Ok(BuiltExpression {
             expr: self.expr,
             g: self.graph,
-        }
+        })
     }
 }
 
-pub(crate) fn make_nfa_builder(expr: &ParsedExpression) -> NfaBuilder {
-    NfaBuilder::new(expr)
+pub(crate) fn make_nfa_builder(expr: &ParsedExpression, cc: &CompileContext) -> NfaBuilder {
+    NfaBuilder::new(expr, cc)
 }
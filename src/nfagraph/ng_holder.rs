@@ -1,5 +1,5 @@
 use crate::parser::PosFlags;
-use crate::util::{CharReach, ReportId};
+use crate::util::{make_caseless, CharReach, ReportId, Ue2Literal};
 use petgraph::graph::{DefaultIx, NodeIndex};
 use petgraph::{Direction, Graph};
 use std::collections::HashSet;
@@ -17,8 +17,12 @@ pub(crate) struct NfaGraphVertexProps {
     pub(in crate::nfagraph) assert_flags: PosFlags,
 }
 
-#[derive(Default)]
-struct NfaGraphEdgeProps {}
+#[derive(Clone, Default)]
+pub(crate) struct NfaGraphEdgeProps {
+    /// Word boundary assertions that must hold between the characters of the
+    /// source and target vertices.
+    pub(in crate::nfagraph) assert_flags: PosFlags,
+}
 
 pub(crate) struct NgHolder {
     inner: Graph<NfaGraphVertexProps, NfaGraphEdgeProps>,
@@ -61,6 +65,88 @@ impl NgHolder {
         self.inner.add_node(v)
     }
 
+    /// Adds an edge from `u` to `v`, unless one is already present.
+    pub(in crate::nfagraph) fn add_edge(&mut self, u: NfaVertex, v: NfaVertex) {
+        self.inner.update_edge(u, v, NfaGraphEdgeProps::default());
+    }
+
+    /// Adds an edge from `u` to `v` carrying the given assertion flags,
+    /// replacing those of any edge already present.
+    pub(in crate::nfagraph) fn add_edge_with_flags(
+        &mut self,
+        u: NfaVertex,
+        v: NfaVertex,
+        assert_flags: PosFlags,
+    ) {
+        self.inner
+            .update_edge(u, v, NfaGraphEdgeProps { assert_flags });
+    }
+
+    /// Removes the edge from `u` to `v`, if any.
+    pub(in crate::nfagraph) fn remove_edge(&mut self, u: NfaVertex, v: NfaVertex) {
+        if let Some(e) = self.inner.find_edge(u, v) {
+            self.inner.remove_edge(e);
+        }
+    }
+
+    /// Returns every edge, as `(source, target, assertion flags)`.
+    pub(in crate::nfagraph) fn edges(&self) -> Vec<(NfaVertex, NfaVertex, PosFlags)> {
+        self.inner
+            .edge_indices()
+            .map(|e| {
+                let (u, v) = self.inner.edge_endpoints(e).expect("valid edge");
+                (u, v, self.inner[e].assert_flags)
+            })
+            .collect()
+    }
+
+    /// Removes the given non-special vertices and their edges.
+    ///
+    /// Vertex handles other than those of the special vertices are
+    /// invalidated.
+    pub(in crate::nfagraph) fn remove_vertices(&mut self, mut vs: Vec<NfaVertex>) {
+        // Removal moves the last vertex into the freed slot, so go from the
+        // highest index down.
+        vs.sort_unstable();
+        vs.dedup();
+        for v in vs.into_iter().rev() {
+            debug_assert!(!self.is_special(v));
+            self.inner.remove_node(v);
+        }
+    }
+
+    /// Returns the predecessors of `v`.
+    pub(in crate::nfagraph) fn predecessors(
+        &self,
+        v: NfaVertex,
+    ) -> impl Iterator<Item = NfaVertex> + '_ {
+        self.inner.neighbors_directed(v, Direction::Incoming)
+    }
+
+    /// Returns `true` if there is an edge from `u` to `v`.
+    pub(in crate::nfagraph) fn has_edge(&self, u: NfaVertex, v: NfaVertex) -> bool {
+        self.inner.find_edge(u, v).is_some()
+    }
+
+    /// Returns `true` if `v` is one of the special vertices (start, start
+    /// dot-star, accept and accept EOD).
+    pub(in crate::nfagraph) fn is_special(&self, v: NfaVertex) -> bool {
+        v == self.start || v == self.start_ds || v == self.accept || v == self.accept_eod
+    }
+
+    /// Returns all vertices in the graph, in order of creation.
+    pub(in crate::nfagraph) fn vertices(&self) -> impl Iterator<Item = NfaVertex> + '_ {
+        self.inner.node_indices()
+    }
+
+    /// Returns the successors of `v`.
+    pub(in crate::nfagraph) fn successors(
+        &self,
+        v: NfaVertex,
+    ) -> impl Iterator<Item = NfaVertex> + '_ {
+        self.inner.neighbors_directed(v, Direction::Outgoing)
+    }
+
     pub(in crate::nfagraph) fn num_vertices(&self) -> usize {
         self.inner.node_count()
     }
@@ -95,6 +181,42 @@ impl NgHolder {
                 .find_edge(self.start_ds, self.accept_eod)
                 .is_some()
     }
+
+    /// Returns `true` if the graph matches at every offset, whatever the
+    /// data: that is, if `start_ds` is wired straight to `accept` without
+    /// any assertion.
+    pub(crate) fn matches_everywhere(&self) -> bool {
+        self.inner
+            .find_edge(self.start_ds, self.accept)
+            .map_or(false, |e| self.inner[e].assert_flags.is_empty())
+    }
+}
+
+/// Builds a graph matching `lit`, raising `reports` at its end.
+///
+/// The literal is only matched at the start of data if `anchored` is set, and
+/// only at the end of data if `eod` is set.
+pub(crate) fn make_literal_graph(
+    lit: &Ue2Literal,
+    anchored: bool,
+    eod: bool,
+    reports: &HashSet<ReportId>,
+) -> NgHolder {
+    let mut g = NgHolder::new();
+    let mut u = if anchored { g.start } else { g.start_ds };
+    for e in lit {
+        let v = g.add_vertex();
+        let mut cr = CharReach::from_char(e.c);
+        if e.nocase {
+            make_caseless(&mut cr);
+        }
+        g[v].char_reach = cr;
+        g.add_edge(u, v);
+        u = v;
+    }
+    g[u].reports = reports.clone();
+    g.add_edge(u, if eod { g.accept_eod } else { g.accept });
+    g
 }
 
 pub(crate) type NfaVertex = NodeIndex<DefaultIx>;
@@ -0,0 +1,83 @@
+//! Construction of runtime NFAs from NFA graphs.
+
+use crate::nfa::{Nfa, NfaStateInfo};
+use crate::nfagraph::{NfaVertex, NgHolder};
+use std::collections::HashMap;
+use std::convert::TryInto;
+
+/// Adds the states of `g` to `nfa`, alongside any states already there.
+fn add_graph(nfa: &mut Nfa, g: &NgHolder) {
+    let base = nfa.states.len();
+    let mut state_ids: HashMap<NfaVertex, u32> = HashMap::new();
+    for v in g.vertices().filter(|&v| !g.is_special(v)) {
+        let id: u32 = (base + state_ids.len())
+            .try_into()
+            .expect("too many NFA states");
+        state_ids.insert(v, id);
+    }
+
+    let mut order: Vec<_> = state_ids.iter().map(|(&v, &id)| (id, v)).collect();
+    order.sort();
+    for (_, v) in order {
+        let mut succ: Vec<u32> = g
+            .successors(v)
+            .filter_map(|t| state_ids.get(&t).copied())
+            .collect();
+        succ.sort_unstable();
+        succ.dedup();
+
+        let mut reports: Vec<_> = g[v].reports.iter().copied().collect();
+        reports.sort_unstable();
+
+        nfa.states.push(NfaStateInfo {
+            reach: g[v].char_reach,
+            succ,
+            reports: if g.has_edge(v, g.accept) {
+                reports.clone()
+            } else {
+                Vec::new()
+            },
+            reports_eod: if g.has_edge(v, g.accept_eod) {
+                reports
+            } else {
+                Vec::new()
+            },
+        });
+    }
+
+    add_initial_states(&mut nfa.init, g, g.start, &state_ids);
+    add_initial_states(&mut nfa.init_ds, g, g.start_ds, &state_ids);
+}
+
+/// Indexes the successors of `start` by the bytes they are entered on.
+fn add_initial_states(
+    table: &mut [Vec<u32>],
+    g: &NgHolder,
+    start: NfaVertex,
+    state_ids: &HashMap<NfaVertex, u32>,
+) {
+    for t in g.successors(start) {
+        if let Some(&id) = state_ids.get(&t) {
+            let cr = g[t].char_reach;
+            let mut c = cr.find_first();
+            while let Some(b) = c {
+                if !table[b as usize].contains(&id) {
+                    table[b as usize].push(id);
+                }
+                c = cr.find_next(b);
+            }
+        }
+    }
+}
+
+/// Builds a single runtime NFA running all the given graphs side by side.
+pub(crate) fn construct_nfa<'g, I>(graphs: I) -> Nfa
+where
+    I: IntoIterator<Item = &'g NgHolder>,
+{
+    let mut nfa = Nfa::default();
+    for g in graphs {
+        add_graph(&mut nfa, g);
+    }
+    nfa
+}
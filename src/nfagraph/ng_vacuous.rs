@@ -0,0 +1,93 @@
+//! Handling of the vacuous edges of a graph, which match without consuming
+//! any data.
+
+use super::NgHolder;
+use crate::util::BoundaryReports;
+
+/// Removes the edges wiring the start vertices of `g` straight to its accept
+/// vertices, turning them into boundary reports.
+///
+/// A pattern matching at every offset also gets a vertex matching any byte,
+/// for the matches after offset 0.
+pub(crate) fn remove_vacuous(g: &mut NgHolder, boundary: &mut BoundaryReports) {
+    if g.has_edge(g.start_ds, g.accept) {
+        // Firehose: matches at offset 0 and after every byte.
+        let reports = g[g.start_ds].reports.clone();
+        boundary.report_at_0.extend(&reports);
+        g.remove_edge(g.start_ds, g.accept);
+
+        let v = g.add_vertex();
+        g[v].char_reach.set_all();
+        g[v].reports = reports;
+        g.add_edge(g.start, v);
+        g.add_edge(g.start_ds, v);
+        g.add_edge(v, g.accept);
+    }
+
+    if g.has_edge(g.start_ds, g.accept_eod) {
+        // End-anchored: matches at end of data, whatever its length.
+        boundary.report_at_eod.extend(&g[g.start_ds].reports);
+        g.remove_edge(g.start_ds, g.accept_eod);
+    }
+
+    if g.has_edge(g.start, g.accept) {
+        // Start-anchored: matches at offset 0.
+        boundary.report_at_0.extend(&g[g.start].reports);
+        g.remove_edge(g.start, g.accept);
+    }
+
+    if g.has_edge(g.start, g.accept_eod) {
+        // Matches nothing but empty data.
+        boundary.report_at_0_eod.extend(&g[g.start].reports);
+        g.remove_edge(g.start, g.accept_eod);
+    }
+
+    // The start vertices no longer raise anything.
+    let (start, start_ds) = (g.start, g.start_ds);
+    g[start].reports.clear();
+    g[start_ds].reports.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nfagraph::find_min_width;
+    use maplit::hashset;
+
+    #[test]
+    fn firehose() {
+        let mut g = NgHolder::new();
+        let (start, start_ds) = (g.start, g.start_ds);
+        g[start].reports.insert(0);
+        g[start_ds].reports.insert(0);
+        g.add_edge(g.start, g.accept);
+        g.add_edge(g.start_ds, g.accept);
+
+        let mut boundary = BoundaryReports::default();
+        remove_vacuous(&mut g, &mut boundary);
+        assert!(!g.is_vacuous());
+        assert_eq!(boundary.report_at_0, hashset! {0});
+        assert!(boundary.report_at_0_eod.is_empty());
+        assert!(boundary.report_at_eod.is_empty());
+        assert_eq!(g.all_reports(), hashset! {0});
+        assert_eq!(find_min_width(&g).value(), Some(1));
+    }
+
+    #[test]
+    fn anchored() {
+        let mut g = NgHolder::new();
+        let (start, start_ds) = (g.start, g.start_ds);
+        g[start].reports.insert(1);
+        g[start_ds].reports.insert(2);
+        g.add_edge(g.start, g.accept_eod);
+        g.add_edge(g.start_ds, g.accept_eod);
+
+        let mut boundary = BoundaryReports::default();
+        remove_vacuous(&mut g, &mut boundary);
+        assert!(!g.is_vacuous());
+        assert!(boundary.report_at_0.is_empty());
+        assert_eq!(boundary.report_at_0_eod, hashset! {1});
+        assert_eq!(boundary.report_at_eod, hashset! {2});
+        assert!(!find_min_width(&g).is_finite());
+    }
+}
@@ -0,0 +1,57 @@
+//! Functions for finding the minimum width of the data a graph can match.
+
+use super::{NfaVertex, NgHolder};
+use crate::util::Depth;
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+
+/// Computes the length of the shortest data that can reach an accept vertex
+/// of `g`, or infinity if no data can.
+pub(crate) fn find_min_width(g: &NgHolder) -> Depth {
+    let mut dist: Vec<Option<usize>> = vec![None; g.num_vertices()];
+    let mut queue: VecDeque<NfaVertex> = VecDeque::new();
+    for &s in &[g.start, g.start_ds] {
+        dist[s.index()] = Some(0);
+        queue.push_back(s);
+    }
+
+    // Every non-special vertex consumes exactly one byte, so a breadth-first
+    // search visits vertices in order of depth.
+    while let Some(u) = queue.pop_front() {
+        let d = dist[u.index()].expect("queued vertices have a depth");
+        if u == g.accept || u == g.accept_eod {
+            return Depth::try_from(d).unwrap_or_else(|_| Depth::infinity());
+        }
+        for v in g.successors(u) {
+            if dist[v.index()].is_none() {
+                dist[v.index()] = Some(if g.is_special(v) { d } else { d + 1 });
+                queue.push_back(v);
+            }
+        }
+    }
+
+    Depth::infinity()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::find_min_width;
+    use crate::nfagraph::{make_literal_graph, NgHolder};
+    use crate::util::Ue2Literal;
+    use std::collections::HashSet;
+
+    #[test]
+    fn min_width() {
+        let mut lit = Ue2Literal::default();
+        for &c in b"abc" {
+            lit.push(c, false);
+        }
+        let g = make_literal_graph(&lit, false, false, &HashSet::new());
+        assert_eq!(find_min_width(&g).value(), Some(3));
+
+        let mut g = NgHolder::new();
+        let v = g.add_vertex();
+        g.add_edge(g.start, v);
+        assert!(!find_min_width(&g).is_finite());
+    }
+}
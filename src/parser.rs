@@ -1,60 +1,154 @@
 mod ascii_component_class;
 mod build_state;
+mod capture_names;
 mod component;
 mod component_alternation;
+mod component_assertion;
+mod component_atomic_group;
+mod component_back_reference;
+mod component_boundary;
 mod component_class;
+mod component_cond_reference;
+mod component_repeat;
 mod component_sequence;
+mod component_word_boundary;
 mod const_component_visitor;
 mod control_verbs;
 #[cfg(test)]
 mod dump;
+mod logical_combination;
 mod parser_util;
 mod position;
 mod position_info;
 mod prefilter;
 mod shortcut_literal;
+mod unsupported;
+mod utf8_component_class;
 
-pub(crate) use build_state::make_glushkov_build_state;
+pub(crate) use build_state::{make_glushkov_build_state, replace_epsilons};
+pub(crate) use capture_names::capture_names;
 pub(crate) use component::Component;
+pub(crate) use logical_combination::{parse_combination, ParsedLogical};
 pub(crate) use parser_util::ParseMode;
 pub(crate) use position::{PosFlags, Position};
 pub(crate) use position_info::PositionInfo;
 pub(crate) use prefilter::prefilter_tree;
 pub(crate) use shortcut_literal::shortcut_literal;
+pub(crate) use unsupported::check_unsupported;
+pub(crate) use utf8_component_class::{ByteSequences, Utf8Dfa};
 
 pub(in crate::parser) use build_state::GlushkovBuildState;
 pub(in crate::parser) use component::walk_component;
 pub(in crate::parser) use component_alternation::ComponentAlternation;
-pub(in crate::parser) use component_class::{get_literal_component_class, ComponentClass};
+pub(in crate::parser) use component_assertion::{AssertionDir, ComponentAssertion};
+pub(in crate::parser) use component_atomic_group::ComponentAtomicGroup;
+pub(in crate::parser) use component_back_reference::{BackRef, ComponentBackReference};
+pub(in crate::parser) use component_boundary::{Boundary, ComponentBoundary};
+pub(in crate::parser) use component_class::{
+    get_component_class, get_literal_component_class, ComponentClass, PredefinedClass,
+};
+pub(in crate::parser) use component_cond_reference::{ComponentCondReference, Condition};
+pub(in crate::parser) use component_repeat::{ComponentRepeat, RepeatType};
 pub(in crate::parser) use component_sequence::ComponentSequence;
+pub(in crate::parser) use component_word_boundary::ComponentWordBoundary;
 pub(in crate::parser) use const_component_visitor::ConstComponentVisitor;
 
 use crate::parser::control_verbs::read_control_verbs;
 use crate::util::compile_error::{CompileError, ErrorKind};
-use nom::{bytes::complete::take, character::complete::char, IResult};
+use crate::util::{ucp_property, CodePointSet};
+use nom::branch::alt;
+use nom::bytes::complete::{
+    is_not, tag, take, take_until, take_while, take_while1, take_while_m_n,
+};
+use nom::character::complete::{
+    alpha1, char, digit0, digit1, hex_digit1, oct_digit1, one_of, satisfy,
+};
+use nom::combinator::{map, not, opt, recognize, rest};
+use nom::sequence::{delimited, pair, preceded, terminated};
+use nom::IResult;
+use std::collections::HashSet;
 use std::mem;
 
+/// Largest bound accepted in a `{m,n}` quantifier.
+const MAX_REPEAT_BOUND: u32 = 65535;
+
 /// Structure representing current state as we're parsing (current sequence,
 /// current options). Stored in the 'sequences' vector.
 struct ExprState {
     seq: ComponentSequence, // Current sequence
     offset: usize,          // Offset seq was entered, for error reporting
     mode: ParseMode,        // Current mode flags
+    kind: GroupKind,        // What the entered sequence is
 }
 
 impl ExprState {
-    fn new(seq: ComponentSequence, offset: usize, mode: ParseMode) -> Self {
-        Self { seq, offset, mode }
+    fn new(seq: ComponentSequence, offset: usize, mode: ParseMode, kind: GroupKind) -> Self {
+        Self {
+            seq,
+            offset,
+            mode,
+            kind,
+        }
     }
 }
 
+/// Kind of group a sequence is entered for, which decides the component it
+/// becomes once complete.
+enum GroupKind {
+    /// A capturing or non-capturing group.
+    Plain,
+    /// A lookaround assertion, possibly negated.
+    Assertion(AssertionDir, bool),
+    /// A lookaround assertion used as the condition of the enclosing
+    /// conditional subpattern.
+    ConditionAssertion(AssertionDir, bool),
+    /// An atomic group.
+    Atomic,
+    /// A conditional subpattern, whose condition is not known yet while its
+    /// assertion is being read.
+    Conditional(Option<Condition>),
+}
+
+/// Meaning of a backslash escape.
+enum Escape {
+    /// A single character.
+    Char(char),
+    /// A shorthand class, possibly negated.
+    Class(PredefinedClass, bool),
+    /// An anchor.
+    Boundary(Boundary),
+    /// A word boundary assertion, possibly negated.
+    WordBoundary(bool),
+    /// The code points of a Unicode property, negation applied.
+    Property(CodePointSet),
+    /// A backreference.
+    BackReference(BackRef),
+}
+
 /// Adds a literal to the current sequence.
+///
+/// In UTF-8 mode, a non-ASCII character is matched as the sequence of bytes
+/// encoding it, as is an ASCII one with non-ASCII other cases (like `k` and
+/// the Kelvin sign) in caseless mode.
 fn add_literal(
     current_seq: &mut ComponentSequence,
     c: char,
     mode: ParseMode,
 ) -> Result<(), CompileError> {
-    let cc = get_literal_component_class(c, mode.caseless)?;
+    let has_non_ascii_case = || {
+        let mut cps = CodePointSet::default();
+        cps.set(u32::from(c));
+        cps.make_caseless();
+        cps.ranges().iter().any(|&(_, hi)| hi > 0x7f)
+    };
+    let cc = if mode.utf8 && (!c.is_ascii() || (mode.caseless && has_non_ascii_case())) {
+        let mut cc = get_component_class(mode);
+        cc.add(c)?;
+        cc.finalize();
+        cc
+    } else {
+        get_literal_component_class(c, mode.caseless)?
+    };
     current_seq.add_component(cc.into());
     Ok(())
 }
@@ -75,6 +169,9 @@ struct Context<'p> {
 
     /// Current sequence being appended to.
     current_seq: ComponentSequence,
+
+    /// Names of the named capturing groups seen so far.
+    group_names: HashSet<&'p str>,
 }
 
 impl<'p> Context<'p> {
@@ -89,21 +186,28 @@ impl<'p> Context<'p> {
             sequences: Vec::new(),
             group_index: 1,
             current_seq,
+            group_names: HashSet::new(),
         }
     }
 
-    fn push_sequence(&mut self, ts: &'p str) {
+    fn push_sequence(&mut self, ts: &'p str, capturing: bool, kind: GroupKind) {
         let mut seq = ComponentSequence::default();
-        seq.capture_index = Some(self.group_index);
-        self.group_index += 1;
+        if capturing {
+            seq.capture_index = Some(self.group_index);
+            self.group_index += 1;
+        }
         mem::swap(&mut self.current_seq, &mut seq);
-        self.sequences
-            .push(ExprState::new(seq, ts.len() - self.ptr.len(), self.mode));
+        self.sequences.push(ExprState::new(
+            seq,
+            self.ptr.len() - ts.len(),
+            self.mode,
+            kind,
+        ));
     }
 
     fn pop_sequence(&mut self) -> Result<(), CompileError> {
-        let (mut seq, mode) = if let Some(v) = self.sequences.pop() {
-            (v.seq, v.mode)
+        let (mut seq, offset, mode, kind) = if let Some(v) = self.sequences.pop() {
+            (v.seq, v.offset, v.mode, v.kind)
         } else {
             return Err(CompileError::new(
                 ErrorKind::LocatedParse,
@@ -112,13 +216,131 @@ impl<'p> Context<'p> {
         };
         mem::swap(&mut self.current_seq, &mut seq);
         seq.finalize();
-        self.current_seq.add_component(seq.into());
+        let component = match kind {
+            GroupKind::Plain => seq.into(),
+            GroupKind::Assertion(dir, negated) => {
+                ComponentAssertion::new(dir, negated, seq, offset).into()
+            }
+            GroupKind::ConditionAssertion(dir, negated) => {
+                let assertion = ComponentAssertion::new(dir, negated, seq, offset);
+                let state = self
+                    .sequences
+                    .last_mut()
+                    .expect("condition assertions are read inside conditionals");
+                state.kind =
+                    GroupKind::Conditional(Some(Condition::Assertion(Box::new(assertion))));
+                self.mode = mode;
+                return Ok(());
+            }
+            GroupKind::Atomic => ComponentAtomicGroup::new(seq, offset).into(),
+            GroupKind::Conditional(condition) => ComponentCondReference::new(
+                condition.expect("the condition is read before the branches"),
+                seq,
+                offset,
+            )
+            .into(),
+        };
+        self.current_seq.add_component(component);
         self.mode = mode;
         Ok(())
     }
 
     fn enter_capturing_group(&mut self, ts: &'p str) {
-        self.push_sequence(ts);
+        self.push_sequence(ts, true, GroupKind::Plain);
+    }
+
+    fn enter_named_capturing_group(
+        &mut self,
+        ts: &'p str,
+        name: &'p str,
+    ) -> Result<(), CompileError> {
+        if !self.group_names.insert(name) {
+            return Err(self.located_error(
+                ts,
+                &format!("Two named subpatterns use the name '{}'", name),
+            ));
+        }
+        self.push_sequence(ts, true, GroupKind::Plain);
+        self.current_seq.capture_name = Some(name.to_owned());
+        Ok(())
+    }
+
+    fn enter_non_capturing_group(&mut self, ts: &'p str) {
+        self.push_sequence(ts, false, GroupKind::Plain);
+    }
+
+    /// Reads the condition of the conditional subpattern starting at `ts`,
+    /// following at `p`, and enters its sequence. Returns the input
+    /// following the condition.
+    fn enter_conditional(&mut self, ts: &'p str, p: &'p str) -> Result<&'p str, CompileError> {
+        if let Ok((rest, (dir, negated))) = assertion_start(p) {
+            // The assertion is read as a group of its own, which becomes the
+            // condition once closed.
+            self.push_sequence(ts, false, GroupKind::Conditional(None));
+            self.push_sequence(p, false, GroupKind::ConditionAssertion(dir, negated));
+            return Ok(rest);
+        }
+
+        let (rest, condition) = terminated(condition, char(')'))(p)
+            .map_err(|_| self.located_error(ts, "Invalid condition after (?("))?;
+        let condition = match condition {
+            RawCondition::Index(sign, digits) => Condition::Index(
+                self.group_reference(sign, digits)
+                    .ok_or_else(|| self.located_error(ts, "Invalid condition after (?("))?,
+            ),
+            RawCondition::Name(name) => Condition::Name(name.to_owned()),
+            RawCondition::Recursion => Condition::Recursion,
+            RawCondition::Define => Condition::Define,
+        };
+        self.push_sequence(ts, false, GroupKind::Conditional(Some(condition)));
+        Ok(rest)
+    }
+
+    /// Resolves a group number, relative to the next capturing group if
+    /// signed, into a group index.
+    fn group_reference(&self, sign: Option<char>, digits: &str) -> Option<u32> {
+        let n = digits.parse::<u32>().ok()?;
+        match sign {
+            None => Some(n),
+            Some('+') if n > 0 => self.group_index.checked_add(n - 1),
+            Some('-') if n > 0 => self.group_index.checked_sub(n).filter(|&i| i > 0),
+            _ => None,
+        }
+    }
+
+    /// Adds an alternation to the current sequence, which may have at most two
+    /// branches in a conditional subpattern.
+    fn add_alternation(&mut self, ts: &str) -> Result<(), CompileError> {
+        if let Some(ExprState {
+            kind: GroupKind::Conditional(_),
+            ..
+        }) = self.sequences.last()
+        {
+            if self.current_seq.num_branches() >= 2 {
+                return Err(self
+                    .located_error(ts, "Conditional subpattern contains more than two branches"));
+            }
+        }
+        self.current_seq.add_alternation();
+        Ok(())
+    }
+
+    /// Returns the current mode with the options named in `on` set and those
+    /// in `off` cleared.
+    fn mode_with_options(&self, on: &str, off: &str) -> ParseMode {
+        let mut mode = self.mode;
+        for (options, value) in &[(on, true), (off, false)] {
+            for c in options.chars() {
+                match c {
+                    'i' => mode.caseless = *value,
+                    'm' => mode.multiline = *value,
+                    's' => mode.dotall = *value,
+                    'x' => mode.ignore_space = *value,
+                    _ => unreachable!("only option letters are read"),
+                }
+            }
+        }
+        mode
     }
 
     fn exit_group(&mut self) -> Result<(), CompileError> {
@@ -126,24 +348,488 @@ impl<'p> Context<'p> {
         Ok(())
     }
 
+    /// Builds a parse error located at the start of `ts`.
+    fn located_error(&self, ts: &str, why: &str) -> CompileError {
+        self.locate(CompileError::new(ErrorKind::LocatedParse, why), ts)
+    }
+
+    /// Adds the location of the start of `ts` to a parse error.
+    fn locate(&self, mut e: CompileError, ts: &str) -> CompileError {
+        e.reason
+            .push_str(&format!(" at index {}", self.ptr.len() - ts.len()));
+        e
+    }
+
+    /// Reads the escape sequence whose backslash is at the start of `ts`, and
+    /// returns the input following it.
+    fn read_escape(&self, ts: &'p str, in_class: bool) -> Result<(&'p str, Escape), CompileError> {
+        let (p, c) = take_any(&ts[1..])
+            .map_err(|_| self.located_error(ts, "Pattern ends with an unterminated escape"))?;
+
+        let class = |c, negated| Ok((p, Escape::Class(c, negated)));
+        let ch = |c| Ok((p, Escape::Char(c)));
+        match c {
+            'd' => class(PredefinedClass::Digit, false),
+            'D' => class(PredefinedClass::Digit, true),
+            'h' => class(PredefinedClass::Horz, false),
+            'H' => class(PredefinedClass::Horz, true),
+            's' => class(PredefinedClass::Space, false),
+            'S' => class(PredefinedClass::Space, true),
+            'v' => class(PredefinedClass::Vert, false),
+            'V' => class(PredefinedClass::Vert, true),
+            'w' => class(PredefinedClass::Word, false),
+            'W' => class(PredefinedClass::Word, true),
+            'p' | 'P' => self.read_property(ts, p, c == 'P'),
+            'A' if !in_class => Ok((p, Escape::Boundary(Boundary::BeginString))),
+            'z' if !in_class => Ok((p, Escape::Boundary(Boundary::EndString))),
+            'Z' if !in_class => Ok((p, Escape::Boundary(Boundary::EndStringOptionalLf))),
+            'a' => ch('\x07'),
+            'e' => ch('\x1b'),
+            'f' => ch('\x0c'),
+            'n' => ch('\n'),
+            'r' => ch('\r'),
+            't' => ch('\t'),
+            // Backspace, inside a class only.
+            'b' if in_class => ch('\x08'),
+            'b' => Ok((p, Escape::WordBoundary(false))),
+            'B' if !in_class => Ok((p, Escape::WordBoundary(true))),
+            'x' => {
+                let (rest, value) = if let Ok((rest, digits)) =
+                    delimited(char::<&str, ()>('{'), hex_digit1, char('}'))(p)
+                {
+                    (rest, u32::from_str_radix(digits, 16).ok())
+                } else if char::<&str, ()>('{')(p).is_ok() {
+                    return Err(self.located_error(ts, "Invalid hexadecimal escape sequence"));
+                } else {
+                    let (rest, digits) =
+                        take_while_m_n::<_, _, ()>(0, 2, |c: char| c.is_ascii_hexdigit())(p)
+                            .expect("cannot fail");
+                    (rest, Some(u32::from_str_radix(digits, 16).unwrap_or(0)))
+                };
+                self.escaped_code_point(ts, rest, value)
+            }
+            'o' => {
+                let (rest, digits) = delimited(char::<&str, ()>('{'), oct_digit1, char('}'))(p)
+                    .map_err(|_| self.located_error(ts, "Invalid octal escape sequence"))?;
+                self.escaped_octal(ts, rest, u32::from_str_radix(digits, 8).ok())
+            }
+            '0' => {
+                let (rest, digits) = take_while_m_n::<_, _, ()>(0, 2, |c: char| c.is_digit(8))(p)
+                    .expect("cannot fail");
+                self.escaped_code_point(ts, rest, Some(u32::from_str_radix(digits, 8).unwrap_or(0)))
+            }
+            'c' => {
+                let (rest, x) =
+                    take_any(p).map_err(|_| self.located_error(ts, "\\c at end of pattern"))?;
+                if !x.is_ascii() {
+                    return Err(
+                        self.located_error(ts, "\\c must be followed by an ASCII character")
+                    );
+                }
+                Ok((
+                    rest,
+                    Escape::Char(char::from(x.to_ascii_uppercase() as u8 ^ 0x40)),
+                ))
+            }
+            'g' if !in_class => self.read_g_escape(ts, p),
+            'k' if !in_class => {
+                let (rest, name) = alt((
+                    delimited(char('<'), group_name, char('>')),
+                    delimited(char('\''), group_name, char('\'')),
+                    delimited(char('{'), group_name, char('}')),
+                ))(p)
+                .map_err(|_| {
+                    self.located_error(
+                        ts,
+                        "\\k is not followed by a braced, angle-bracketed, or quoted name",
+                    )
+                })?;
+                Ok((rest, Escape::BackReference(BackRef::Name(name.to_owned()))))
+            }
+            '1'..='9' if !in_class => {
+                let (rest, digits) = digit0::<_, ()>(p).expect("cannot fail");
+                let n = ts[1..1 + c.len_utf8() + digits.len()]
+                    .parse::<u32>()
+                    .map_err(|_| self.located_error(ts, "Invalid backreference"))?;
+                Ok((rest, Escape::BackReference(BackRef::Index(n))))
+            }
+            // Octal, inside a class.
+            '1'..='7' => {
+                let (rest, digits) = take_while_m_n::<_, _, ()>(0, 2, |c: char| c.is_digit(8))(p)
+                    .expect("cannot fail");
+                let value = u32::from_str_radix(&ts[1..2 + digits.len()], 8).ok();
+                self.escaped_octal(ts, rest, value)
+            }
+            '8' | '9' => ch(c),
+            'K' => Err(self.located_error(ts, "\\K is not supported")),
+            'C' => Err(self.located_error(ts, "\\C is not supported")),
+            'G' if !in_class => Err(self.located_error(ts, "\\G is not supported")),
+            'R' if !in_class => Err(self.located_error(ts, "\\R is not supported")),
+            'X' if !in_class => Err(self.located_error(ts, "\\X is not supported")),
+            c if c.is_ascii_alphanumeric() => Err(self.located_error(ts, "Unrecognised escape")),
+            // Any other escaped character stands for itself.
+            c => {
+                self.check_literal(ts, c)?;
+                ch(c)
+            }
+        }
+    }
+
+    /// Reads the reference of a `\g` escape starting at `ts`, which follows
+    /// at `p`.
+    fn read_g_escape(&self, ts: &str, p: &'p str) -> Result<(&'p str, Escape), CompileError> {
+        if alt((char::<&str, ()>('<'), char('\'')))(p).is_ok() {
+            return Err(self.located_error(ts, "Subroutine calls and recursion are not supported"));
+        }
+        if let Ok((rest, (sign, digits))) = alt((
+            delimited(char('{'), signed_number, char('}')),
+            signed_number,
+        ))(p)
+        {
+            let index = match sign {
+                Some('+') => None,
+                _ => self.group_reference(sign, digits),
+            }
+            .ok_or_else(|| self.located_error(ts, "Invalid backreference"))?;
+            return Ok((rest, Escape::BackReference(BackRef::Index(index))));
+        }
+        let (rest, name) = delimited(char('{'), group_name, char('}'))(p)
+            .map_err(|_| {
+                self.located_error(ts, "\\g is not followed by a braced, angle-bracketed, or quoted name/number or by a plain number")
+            })?;
+        Ok((rest, Escape::BackReference(BackRef::Name(name.to_owned()))))
+    }
+
+    /// Reads the name of the Unicode property escape starting at `ts`, which
+    /// follows at `p` as either a single letter or a braced name, possibly
+    /// negated with a caret.
+    fn read_property(
+        &self,
+        ts: &str,
+        p: &'p str,
+        mut negated: bool,
+    ) -> Result<(&'p str, Escape), CompileError> {
+        let (rest, name) = if let Ok((rest, name)) =
+            delimited(char::<&str, ()>('{'), take_until("}"), char('}'))(p)
+        {
+            (rest, name)
+        } else if char::<&str, ()>('{')(p).is_ok() {
+            return Err(self.located_error(ts, "Malformed \\P or \\p sequence"));
+        } else {
+            let (rest, c) =
+                take_any(p).map_err(|_| self.located_error(ts, "Malformed \\P or \\p sequence"))?;
+            (rest, &p[..c.len_utf8()])
+        };
+        let name = if let Some(name) = name.strip_prefix('^') {
+            negated = !negated;
+            name
+        } else {
+            name
+        };
+
+        // Caseless cased letter categories match letters of any case.
+        let name = match name {
+            "Lu" | "Ll" | "Lt" if self.mode.caseless => "L&",
+            _ => name,
+        };
+        let mut cps = ucp_property(name)
+            .ok_or_else(|| self.located_error(ts, "Unknown property name after \\P or \\p"))?;
+        if negated {
+            cps.flip();
+        }
+        Ok((rest, Escape::Property(cps)))
+    }
+
+    /// Checks that the character `c` at the start of `ts` may appear
+    /// unescaped in the pattern.
+    fn check_literal(&self, ts: &str, c: char) -> Result<(), CompileError> {
+        if !c.is_ascii() && !self.mode.utf8 {
+            return Err(
+                self.located_error(ts, "Non-ASCII characters are only supported in UTF-8 mode")
+            );
+        }
+        Ok(())
+    }
+
+    /// Turns the value of a numeric escape starting at `ts` into a character.
+    fn escaped_code_point(
+        &self,
+        ts: &str,
+        rest: &'p str,
+        value: Option<u32>,
+    ) -> Result<(&'p str, Escape), CompileError> {
+        let c = value
+            .and_then(std::char::from_u32)
+            .ok_or_else(|| self.located_error(ts, "Invalid character value in escape sequence"))?;
+        Ok((rest, Escape::Char(c)))
+    }
+
+    /// Turns the value of an octal escape starting at `ts` into a character.
+    fn escaped_octal(
+        &self,
+        ts: &str,
+        rest: &'p str,
+        value: Option<u32>,
+    ) -> Result<(&'p str, Escape), CompileError> {
+        if !self.mode.utf8 && value.map_or(false, |v| v > 0xff) {
+            return Err(self.located_error(ts, "Octal value is greater than \\377"));
+        }
+        self.escaped_code_point(ts, rest, value)
+    }
+
+    /// Reads the rest of a bracketed character class whose opening bracket
+    /// is at the start of `ts` and adds it to the current sequence. Returns
+    /// the input following the class.
+    fn read_class(&mut self, ts: &'p str, mut p: &'p str) -> Result<&'p str, CompileError> {
+        let mut cc = get_component_class(self.mode);
+
+        if let Ok((rest, _)) = char::<&str, ()>('^')(p) {
+            cc.negate();
+            p = rest;
+        }
+
+        // A closing bracket right at the start is a literal.
+        if let Ok((rest, _)) = char::<&str, ()>(']')(p) {
+            cc.add(']').map_err(|e| self.locate(e, p))?;
+            p = rest;
+        }
+
+        loop {
+            if let Ok((rest, _)) = char::<&str, ()>(']')(p) {
+                cc.finalize();
+                self.current_seq.add_component(cc.into());
+                return Ok(rest);
+            } else if let Ok((rest, (negated, name))) = posix_named_class(p) {
+                let class = posix_class_by_name(name)
+                    .ok_or_else(|| self.located_error(p, "Invalid POSIX named class"))?;
+                cc.add_predefined(class, negated)
+                    .map_err(|e| self.locate(e, p))?;
+                p = rest;
+            } else if posix_collating_element(p).is_ok() {
+                return Err(self.located_error(p, "Unsupported POSIX collating element"));
+            } else if let Ok((rest, text)) = quoted(p) {
+                let mut q = &p[2..];
+                for c in text.chars() {
+                    self.check_literal(q, c)?;
+                    cc.add(c).map_err(|e| self.locate(e, q))?;
+                    q = &q[c.len_utf8()..];
+                }
+                p = rest;
+            } else if let Ok((rest, _)) = tag::<_, _, ()>("\\E")(p) {
+                p = rest;
+            } else if char::<&str, ()>('\\')(p).is_ok() {
+                let (rest, esc) = self.read_escape(p, true)?;
+                match esc {
+                    Escape::Char(c) => cc.add(c),
+                    Escape::Class(class, negated) => cc.add_predefined(class, negated),
+                    Escape::Property(cps) => cc.add_code_points(&cps),
+                    Escape::Boundary(_) | Escape::WordBoundary(_) | Escape::BackReference(_) => {
+                        unreachable!("assertions and backreferences are not read in classes")
+                    }
+                }
+                .map_err(|e| self.locate(e, p))?;
+                p = rest;
+            } else if let Ok((rest, _)) = char::<&str, ()>('-')(p) {
+                cc.add_dash().map_err(|e| self.locate(e, p))?;
+                p = rest;
+            } else if let Ok((rest, c)) = take_any(p) {
+                self.check_literal(p, c)?;
+                cc.add(c).map_err(|e| self.locate(e, p))?;
+                p = rest;
+            } else {
+                return Err(CompileError::new(
+                    ErrorKind::Parse,
+                    format!(
+                        "Unterminated character class starting at index {}.",
+                        self.ptr.len() - ts.len()
+                    ),
+                ));
+            }
+        }
+    }
+
+    /// Reads a bound of a `{m,n}` quantifier starting at `ts`.
+    fn read_repeat_bound(&self, ts: &str, digits: &str) -> Result<u32, CompileError> {
+        match digits.parse::<u32>() {
+            Ok(n) if n <= MAX_REPEAT_BOUND => Ok(n),
+            _ => Err(self.located_error(ts, "Number too big in {} quantifier")),
+        }
+    }
+
+    /// Applies the quantifier `q` starting at `ts` to the last component, and
+    /// returns the input following it and its lazy or possessive suffix, if
+    /// any, from `p`.
+    fn add_repeat(
+        &mut self,
+        ts: &'p str,
+        p: &'p str,
+        q: Quantifier<'p>,
+    ) -> Result<&'p str, CompileError> {
+        let (min, max) = match q {
+            Quantifier::Star => (0, None),
+            Quantifier::Plus => (1, None),
+            Quantifier::Question => (0, Some(1)),
+            Quantifier::Exactly(n) => {
+                let n = self.read_repeat_bound(ts, n)?;
+                (n, Some(n))
+            }
+            Quantifier::AtLeast(m) => (self.read_repeat_bound(ts, m)?, None),
+            Quantifier::Range(m, n) => {
+                let m = self.read_repeat_bound(ts, m)?;
+                let n = self.read_repeat_bound(ts, n)?;
+                if m > n {
+                    return Err(self.located_error(ts, "Invalid repeat"));
+                }
+                (m, Some(n))
+            }
+        };
+
+        let (p, kind) = repeat_type(p);
+        if !self.current_seq.add_repeat(min, max, kind) {
+            return Err(self.located_error(ts, "Invalid repeat"));
+        }
+        Ok(p)
+    }
+
     fn main(&mut self, ts: &'p str) -> Result<(), CompileError> {
-        if let Ok((p, _)) = char::<&str, ()>('(')(ts) {
+        if self.mode.ignore_space {
+            if let Ok((p, _)) = extended_space(ts) {
+                self.p = p;
+                return Ok(());
+            }
+        }
+
+        if let Ok((p, _)) = tag::<_, _, ()>("(?#")(ts) {
+            let (p, _) = terminated(take_until::<_, _, ()>(")"), char(')'))(p)
+                .map_err(|_| self.located_error(ts, "Missing ) at end of (?# comment"))?;
+            self.p = p;
+        } else if let Ok((p, _)) = tag::<_, _, ()>("(?:")(ts) {
+            self.enter_non_capturing_group(ts);
+            self.p = p;
+        } else if let Ok((p, (on, off, scoped))) = option_setting(ts) {
+            let mode = self.mode_with_options(on, off);
+            if scoped {
+                // The options only apply inside the group.
+                self.enter_non_capturing_group(ts);
+            }
+            self.mode = mode;
+            self.p = p;
+        } else if let Ok((p, (dir, negated))) = preceded(tag("("), assertion_start)(ts) {
+            self.push_sequence(ts, false, GroupKind::Assertion(dir, negated));
+            self.p = p;
+        } else if let Ok((p, _)) = tag::<_, _, ()>("(?>")(ts) {
+            self.push_sequence(ts, false, GroupKind::Atomic);
+            self.p = p;
+        } else if let Ok((p, _)) = tag::<_, _, ()>("(?(")(ts) {
+            self.p = self.enter_conditional(ts, p)?;
+        } else if let Ok((p, name)) = delimited(tag("(?P="), group_name, char(')'))(ts) {
+            let reference = BackRef::Name(name.to_owned());
+            let loc = self.ptr.len() - ts.len();
+            self.current_seq
+                .add_component(ComponentBackReference::new(reference, loc).into());
+            self.p = p;
+        } else if subroutine_call(ts).is_ok() {
+            return Err(self.located_error(ts, "Subroutine calls and recursion are not supported"));
+        } else if tag::<_, _, ()>("(?C")(ts).is_ok() {
+            return Err(self.located_error(ts, "Callouts are not supported"));
+        } else if let Ok((p, name)) = named_group(ts) {
+            self.enter_named_capturing_group(ts, name)?;
+            self.p = p;
+        } else if named_group_start(ts).is_ok() {
+            return Err(self.located_error(ts, "Invalid group name"));
+        } else if tag::<_, _, ()>("(?")(ts).is_ok() {
+            return Err(self.located_error(ts, "Unrecognised character after (?"));
+        } else if let Ok((p, _)) = char::<&str, ()>('(')(ts) {
             self.enter_capturing_group(ts);
             self.p = p;
         } else if let Ok((p, _)) = char::<&str, ()>(')')(ts) {
             self.exit_group()?;
             self.p = p;
         } else if let Ok((p, _)) = char::<&str, ()>('|')(ts) {
-            self.current_seq.add_alternation();
+            self.add_alternation(ts)?;
             self.p = p;
-        } else if let Ok((p, c)) = take_any(ts) {
-            // TODO: Support UTF-8 literals
-            assert!(c.is_ascii());
-            if let Err(mut e) = add_literal(&mut self.current_seq, c, self.mode) {
-                e.reason
-                    .push_str(&format!(" at index {}", self.ptr.len() - self.p.len()));
-                return Err(e);
+        } else if let Ok((p, text)) = quoted(ts) {
+            let mut q = &ts[2..];
+            for c in text.chars() {
+                self.check_literal(q, c)?;
+                add_literal(&mut self.current_seq, c, self.mode).map_err(|e| self.locate(e, q))?;
+                q = &q[c.len_utf8()..];
             }
+            self.p = p;
+        } else if let Ok((p, _)) = tag::<_, _, ()>("\\E")(ts) {
+            // An unmatched end of quote is ignored.
+            self.p = p;
+        } else if char::<&str, ()>('\\')(ts).is_ok() {
+            let (p, esc) = self.read_escape(ts, false)?;
+            match esc {
+                Escape::Char(c) => {
+                    add_literal(&mut self.current_seq, c, self.mode)
+                        .map_err(|e| self.locate(e, ts))?;
+                }
+                Escape::Class(class, negated) => {
+                    let mut cc = get_component_class(self.mode);
+                    cc.add_predefined(class, negated)
+                        .map_err(|e| self.locate(e, ts))?;
+                    cc.finalize();
+                    self.current_seq.add_component(cc.into());
+                }
+                Escape::Property(cps) => {
+                    let mut cc = get_component_class(self.mode);
+                    cc.add_code_points(&cps).map_err(|e| self.locate(e, ts))?;
+                    cc.finalize();
+                    self.current_seq.add_component(cc.into());
+                }
+                Escape::Boundary(bound) => {
+                    self.current_seq
+                        .add_component(ComponentBoundary::new(bound).into());
+                }
+                Escape::WordBoundary(negated) => {
+                    self.current_seq
+                        .add_component(ComponentWordBoundary::new(negated, self.mode.ucp).into());
+                }
+                Escape::BackReference(reference) => {
+                    let loc = self.ptr.len() - ts.len();
+                    self.current_seq
+                        .add_component(ComponentBackReference::new(reference, loc).into());
+                }
+            }
+            self.p = p;
+        } else if posix_named_class(ts).is_ok() {
+            return Err(
+                self.located_error(ts, "POSIX named classes are only supported inside a class")
+            );
+        } else if let Ok((p, _)) = char::<&str, ()>('[')(ts) {
+            self.p = self.read_class(ts, p)?;
+        } else if let Ok((p, _)) = char::<&str, ()>('.')(ts) {
+            let mut cc = get_component_class(self.mode);
+            cc.add_predefined(PredefinedClass::Any, false)
+                .map_err(|e| self.locate(e, ts))?;
+            cc.finalize();
+            self.current_seq.add_component(cc.into());
+            self.p = p;
+        } else if let Ok((p, _)) = char::<&str, ()>('^')(ts) {
+            let bound = if self.mode.multiline {
+                Boundary::BeginLine
+            } else {
+                Boundary::BeginString
+            };
+            self.current_seq
+                .add_component(ComponentBoundary::new(bound).into());
+            self.p = p;
+        } else if let Ok((p, _)) = char::<&str, ()>('$')(ts) {
+            let bound = if self.mode.multiline {
+                Boundary::EndLine
+            } else {
+                Boundary::EndStringOptionalLf
+            };
+            self.current_seq
+                .add_component(ComponentBoundary::new(bound).into());
+            self.p = p;
+        } else if let Ok((p, q)) = quantifier(ts) {
+            self.p = self.add_repeat(ts, p, q)?;
+        } else if let Ok((p, c)) = take_any(ts) {
+            self.check_literal(ts, c)?;
+            add_literal(&mut self.current_seq, c, self.mode).map_err(|e| self.locate(e, ts))?;
             self.p = p
         }
         Ok(())
@@ -174,9 +860,210 @@ impl<'p> Context<'p> {
 pub(crate) fn parse(ptr: &str, global_mode: &mut ParseMode) -> Result<Component, CompileError> {
     let p = read_control_verbs(ptr, 0, global_mode)?;
 
-    let root_seq = Context::new(ptr, p, *global_mode).parse()?;
+    let root = Component::Sequence(Context::new(ptr, p, *global_mode).parse()?);
 
-    Ok(Component::Sequence(root_seq))
+    // Anchors are only supported where they cannot be followed or preceded by
+    // anything matched.
+    root.check_embedded_start_anchor(true)?;
+    root.check_embedded_end_anchor(true)?;
+
+    Ok(root)
+}
+
+/// A quantifier, with the digits of its bounds.
+enum Quantifier<'a> {
+    Star,
+    Plus,
+    Question,
+    Exactly(&'a str),
+    AtLeast(&'a str),
+    Range(&'a str, &'a str),
+}
+
+fn quantifier(input: &str) -> IResult<&str, Quantifier<'_>> {
+    alt((
+        map(char('*'), |_| Quantifier::Star),
+        map(char('+'), |_| Quantifier::Plus),
+        map(char('?'), |_| Quantifier::Question),
+        map(
+            delimited(
+                char('{'),
+                pair(digit1, opt(preceded(char(','), digit0))),
+                char('}'),
+            ),
+            |(m, n): (&str, Option<&str>)| match n {
+                None => Quantifier::Exactly(m),
+                Some("") => Quantifier::AtLeast(m),
+                Some(n) => Quantifier::Range(m, n),
+            },
+        ),
+    ))(input)
+}
+
+/// Parses whitespace or a `#` comment running to the end of the line, both
+/// ignored in extended mode.
+fn extended_space(input: &str) -> IResult<&str, &str> {
+    alt((
+        take_while1(|c| " \t\n\x0b\x0c\r".contains(c)),
+        recognize(pair(char('#'), opt(is_not("\n")))),
+    ))(input)
+}
+
+/// Parses a span quoted with `\Q`, which runs to the next `\E` or the end of
+/// the pattern, returning the quoted text.
+fn quoted(input: &str) -> IResult<&str, &str> {
+    preceded(
+        tag("\\Q"),
+        alt((terminated(take_until("\\E"), tag("\\E")), rest)),
+    )(input)
+}
+
+/// Parses an option setting such as `(?i-s)` or the start of a group with
+/// scoped options such as `(?i:`, returning the options turned on and off and
+/// whether a group is started.
+fn option_setting(input: &str) -> IResult<&str, (&str, &str, bool)> {
+    let options = |i| take_while(|c| "imsx".contains(c))(i);
+    let (rest, (on, off)) =
+        preceded(tag("(?"), pair(options, opt(preceded(char('-'), options))))(input)?;
+    let (rest, end) = alt((char(')'), char(':')))(rest)?;
+    Ok((rest, (on, off.unwrap_or(""), end == ':')))
+}
+
+/// Parses a group name.
+fn group_name(input: &str) -> IResult<&str, &str> {
+    recognize(pair(
+        satisfy(|c| c.is_ascii_alphabetic() || c == '_'),
+        take_while(|c: char| c.is_ascii_alphanumeric() || c == '_'),
+    ))(input)
+}
+
+/// Parses the start of a named capturing group, in any of the `(?<name>`,
+/// `(?'name'` and `(?P<name>` forms, returning its name.
+fn named_group(input: &str) -> IResult<&str, &str> {
+    alt((
+        delimited(alt((tag("(?<"), tag("(?P<"))), group_name, char('>')),
+        delimited(tag("(?'"), group_name, char('\'')),
+    ))(input)
+}
+
+/// Parses the opening of a named capturing group, whatever follows it.
+fn named_group_start(input: &str) -> IResult<&str, &str> {
+    alt((
+        tag("(?P<"),
+        tag("(?'"),
+        terminated(tag("(?<"), not(one_of("=!"))),
+    ))(input)
+}
+
+/// Parses a group number, possibly signed, returning its sign and digits.
+fn signed_number(input: &str) -> IResult<&str, (Option<char>, &str)> {
+    pair(opt(one_of("+-")), digit1)(input)
+}
+
+/// Parses the opening of a lookaround assertion after its parenthesis,
+/// returning its direction and whether it is negated.
+fn assertion_start(input: &str) -> IResult<&str, (AssertionDir, bool)> {
+    alt((
+        map(tag("?="), |_| (AssertionDir::Lookahead, false)),
+        map(tag("?!"), |_| (AssertionDir::Lookahead, true)),
+        map(tag("?<="), |_| (AssertionDir::Lookbehind, false)),
+        map(tag("?<!"), |_| (AssertionDir::Lookbehind, true)),
+    ))(input)
+}
+
+/// Condition of a conditional subpattern, as written.
+enum RawCondition<'a> {
+    Index(Option<char>, &'a str),
+    Name(&'a str),
+    Recursion,
+    Define,
+}
+
+/// Parses the condition of a conditional subpattern after `(?(`, other than
+/// an assertion.
+fn condition(input: &str) -> IResult<&str, RawCondition<'_>> {
+    alt((
+        map(signed_number, |(sign, digits)| {
+            RawCondition::Index(sign, digits)
+        }),
+        map(
+            alt((
+                delimited(char('<'), group_name, char('>')),
+                delimited(char('\''), group_name, char('\'')),
+            )),
+            RawCondition::Name,
+        ),
+        map(
+            alt((
+                recognize(pair(tag("R&"), group_name)),
+                recognize(pair(char('R'), digit0)),
+            )),
+            |_| RawCondition::Recursion,
+        ),
+        map(terminated(tag("DEFINE"), not(group_name)), |_| {
+            RawCondition::Define
+        }),
+        map(group_name, RawCondition::Name),
+    ))(input)
+}
+
+/// Parses the start of a subroutine call or recursion, such as `(?R)`,
+/// `(?1)`, `(?-1)`, `(?&name)` or `(?P>name)`.
+fn subroutine_call(input: &str) -> IResult<&str, &str> {
+    recognize(pair(
+        tag("(?"),
+        alt((tag("R)"), recognize(signed_number), tag("&"), tag("P>"))),
+    ))(input)
+}
+
+/// Parses a POSIX named class such as `[:alpha:]` or `[:^digit:]`,
+/// returning whether it is negated and its name.
+fn posix_named_class(input: &str) -> IResult<&str, (bool, &str)> {
+    delimited(
+        tag("[:"),
+        pair(map(opt(char('^')), |c| c.is_some()), alpha1),
+        tag(":]"),
+    )(input)
+}
+
+/// Parses a POSIX collating element or equivalence class, such as `[.a.]`
+/// or `[=a=]`.
+fn posix_collating_element(input: &str) -> IResult<&str, &str> {
+    alt((
+        delimited(tag("[."), alpha1, tag(".]")),
+        delimited(tag("[="), alpha1, tag("=]")),
+    ))(input)
+}
+
+fn posix_class_by_name(name: &str) -> Option<PredefinedClass> {
+    Some(match name {
+        "alnum" => PredefinedClass::Alnum,
+        "alpha" => PredefinedClass::Alpha,
+        "ascii" => PredefinedClass::Ascii,
+        "blank" => PredefinedClass::Blank,
+        "cntrl" => PredefinedClass::Cntrl,
+        "digit" => PredefinedClass::Digit,
+        "graph" => PredefinedClass::Graph,
+        "lower" => PredefinedClass::Lower,
+        "print" => PredefinedClass::Print,
+        "punct" => PredefinedClass::Punct,
+        "space" => PredefinedClass::Space,
+        "upper" => PredefinedClass::Upper,
+        "word" => PredefinedClass::Word,
+        "xdigit" => PredefinedClass::Xdigit,
+        _ => return None,
+    })
+}
+
+/// Reads the optional suffix making a quantifier lazy or possessive.
+fn repeat_type(input: &str) -> (&str, RepeatType) {
+    if let Ok((p, _)) = char::<&str, ()>('?')(input) {
+        (p, RepeatType::NonGreedy)
+    } else if let Ok((p, _)) = char::<&str, ()>('+')(input) {
+        (p, RepeatType::Possessive)
+    } else {
+        (input, RepeatType::Greedy)
+    }
 }
 
 fn take_any(input: &str) -> IResult<&str, char> {
@@ -241,4 +1128,326 @@ mod tests {
 "
         );
     }
+
+    #[test]
+    fn parse_non_capturing_group() {
+        let mut mode = ParseMode::default();
+        let c = parse("(?:a)(?i:b)(c)", &mut mode).expect("valid");
+        let mut out = String::new();
+        dump_tree(&mut out, &c).unwrap();
+        assert_eq!(
+            out,
+            "SEQUENCE (capture index 0)
+  SEQUENCE (not captured)
+    ASCII CLASS
+      a
+  SEQUENCE (not captured)
+    ASCII CLASS
+      [Bb]
+  SEQUENCE (capture index 1)
+    ASCII CLASS
+      c
+"
+        );
+
+        let c = parse("(?<n>a)", &mut mode).expect("valid");
+        let mut out = String::new();
+        dump_tree(&mut out, &c).unwrap();
+        assert_eq!(
+            out,
+            "SEQUENCE (capture index 0)
+  SEQUENCE (capture index 1) (capture name 'n')
+    ASCII CLASS
+      a
+"
+        );
+    }
+
+    #[test]
+    fn parse_unsupported_constructs() {
+        let mut mode = ParseMode::default();
+        let c = parse("(a)\\1(?<=b)(?>c)(?(?!d)e|f)", &mut mode).expect("valid");
+        let mut out = String::new();
+        dump_tree(&mut out, &c).unwrap();
+        assert_eq!(
+            out,
+            "SEQUENCE (capture index 0)
+  SEQUENCE (capture index 1)
+    ASCII CLASS
+      a
+  BACKREF 1
+  LOOKBEHIND
+    SEQUENCE (not captured)
+      ASCII CLASS
+        b
+  ATOMIC GROUP
+    SEQUENCE (not captured)
+      ASCII CLASS
+        c
+  CONDITIONAL (assertion)
+    NEGATIVE LOOKAHEAD
+      SEQUENCE (not captured)
+        ASCII CLASS
+          d
+    SEQUENCE (not captured)
+      ALTERNATION
+        SEQUENCE (not captured)
+          ASCII CLASS
+            e
+        SEQUENCE (not captured)
+          ASCII CLASS
+            f
+"
+        );
+
+        for (pattern, reason) in &[
+            ("ab\\1", "Backreferences are not supported at index 2"),
+            (
+                "(?<n>a)\\k<n>",
+                "Backreferences are not supported at index 7",
+            ),
+            ("(a)\\g{-1}", "Backreferences are not supported at index 3"),
+            ("x(?P=n)", "Backreferences are not supported at index 1"),
+            (
+                "a(?=b)",
+                "Lookahead assertions are not supported at index 1",
+            ),
+            (
+                "a(?<!b)",
+                "Lookbehind assertions are not supported at index 1",
+            ),
+            ("a(?>b)", "Atomic groups are not supported at index 1"),
+            (
+                "(a)(?(1)b|c)",
+                "Conditional subpatterns are not supported at index 3",
+            ),
+            (
+                "(?(DEFINE)a)",
+                "Conditional subpatterns are not supported at index 0",
+            ),
+        ] {
+            let mut mode = ParseMode::default();
+            let c = parse(pattern, &mut mode).expect("valid");
+            let e = check_unsupported(&c).expect_err("unsupported");
+            assert!(matches!(e.kind(), ErrorKind::LocatedParse));
+            assert_eq!(&e.reason, reason);
+        }
+
+        // Inside a class, digits are octal or literal.
+        let c = parse("[\\101\\8]", &mut mode).expect("valid");
+        let mut out = String::new();
+        dump_tree(&mut out, &c).unwrap();
+        assert_eq!(
+            out,
+            "SEQUENCE (capture index 0)
+  ASCII CLASS
+    [8A]
+"
+        );
+    }
+
+    #[test]
+    fn parse_repeat() {
+        let mut mode = ParseMode::default();
+        let c = parse("a{2,3}?", &mut mode).expect("valid");
+        let mut out = String::new();
+        dump_tree(&mut out, &c).unwrap();
+        assert_eq!(
+            out,
+            "SEQUENCE (capture index 0)
+  REPEAT (2, 3) non-greedy
+    ASCII CLASS
+      a
+"
+        );
+    }
+
+    #[test]
+    fn parse_class() {
+        let mut mode = ParseMode::default();
+        let c = parse("[a-c[:digit:]]", &mut mode).expect("valid");
+        let mut out = String::new();
+        dump_tree(&mut out, &c).unwrap();
+        assert_eq!(
+            out,
+            "SEQUENCE (capture index 0)
+  ASCII CLASS
+    [\\dabc]
+"
+        );
+
+        let e = parse("x[ab", &mut mode).expect_err("unterminated");
+        assert!(matches!(e.kind(), ErrorKind::Parse));
+        assert_eq!(
+            e.reason,
+            "Unterminated character class starting at index 1."
+        );
+    }
+
+    #[test]
+    fn parse_utf8_dot() {
+        let mut mode = ParseMode {
+            utf8: true,
+            ..ParseMode::default()
+        };
+        let c = parse(".", &mut mode).expect("valid");
+        let mut out = String::new();
+        dump_tree(&mut out, &c).unwrap();
+        assert_eq!(
+            out,
+            "SEQUENCE (capture index 0)
+  UTF8 CLASS
+    U+0000-U+0009 U+000B-U+10FFFF
+"
+        );
+    }
+
+    #[test]
+    fn parse_anchors() {
+        let mut mode = ParseMode {
+            multiline: true,
+            ..ParseMode::default()
+        };
+        let c = parse("^\\A$\\z", &mut mode).expect("valid");
+        let mut out = String::new();
+        dump_tree(&mut out, &c).unwrap();
+        assert_eq!(
+            out,
+            "SEQUENCE (capture index 0)
+  BEGIN_LINE
+  BEGIN_STRING
+  END_LINE
+  END_STRING
+"
+        );
+
+        for (pattern, reason) in &[
+            ("a^b", "Embedded start anchors not supported."),
+            ("(^a)+", "Embedded start anchors not supported."),
+            ("a$b", "Embedded end anchors not supported."),
+            ("(a|\\z)b", "Embedded end anchors not supported."),
+        ] {
+            let mut mode = ParseMode::default();
+            let e = parse(pattern, &mut mode).expect_err("invalid");
+            assert!(matches!(e.kind(), ErrorKind::Parse));
+            assert_eq!(&e.reason, reason);
+        }
+        let c = parse("\\bx\\B", &mut mode).expect("valid");
+        let mut out = String::new();
+        dump_tree(&mut out, &c).unwrap();
+        assert_eq!(
+            out,
+            "SEQUENCE (capture index 0)
+  WORD-BOUNDARY
+  ASCII CLASS
+    x
+  NON-WORD-BOUNDARY
+"
+        );
+
+        let e = parse("^*", &mut mode).expect_err("invalid");
+        assert_eq!(e.reason, "Invalid repeat at index 1");
+    }
+
+    #[test]
+    fn parse_invalid_repeat() {
+        for (pattern, reason) in &[
+            ("*a", "Invalid repeat at index 0"),
+            ("(+)", "Invalid repeat at index 1"),
+            ("a**", "Invalid repeat at index 2"),
+            ("a{3,2}", "Invalid repeat at index 1"),
+            ("a{70000}", "Number too big in {} quantifier at index 1"),
+            ("a[z-a]", "Range out of order in character class at index 4"),
+            (
+                "[a-[:digit:]]",
+                "Invalid range in character class at index 3",
+            ),
+            ("[[:foo:]]", "Invalid POSIX named class at index 1"),
+            (
+                "ab\\",
+                "Pattern ends with an unterminated escape at index 2",
+            ),
+            (
+                "a\\x{100}",
+                "Hexadecimal value is greater than \\xFF at index 1",
+            ),
+            (
+                "a\\x{110000}",
+                "Invalid character value in escape sequence at index 1",
+            ),
+            ("a\\x{1", "Invalid hexadecimal escape sequence at index 1"),
+            ("\\o{8}", "Invalid octal escape sequence at index 0"),
+            ("a\\o{400}", "Octal value is greater than \\377 at index 1"),
+            ("[\\777]", "Octal value is greater than \\377 at index 1"),
+            ("a\\c", "\\c at end of pattern at index 1"),
+            ("a\\K", "\\K is not supported at index 1"),
+            ("a\\g{-2}(b)", "Invalid backreference at index 1"),
+            ("a(?(x", "Invalid condition after (?( at index 1"),
+            (
+                "(?(1)a|b|c)",
+                "Conditional subpattern contains more than two branches at index 8",
+            ),
+            (
+                "a(?1)",
+                "Subroutine calls and recursion are not supported at index 1",
+            ),
+            ("(?C1)a", "Callouts are not supported at index 0"),
+            ("a(?y)", "Unrecognised character after (? at index 1"),
+            (
+                "(?<x>a)(?P<x>b)",
+                "Two named subpatterns use the name 'x' at index 7",
+            ),
+            ("a(?<1>b)", "Invalid group name at index 1"),
+            ("a(?#b", "Missing ) at end of (?# comment at index 1"),
+            ("(?P<a-b>)", "Invalid group name at index 0"),
+            ("(?i-q:a)", "Unrecognised character after (? at index 0"),
+            ("a\\i", "Unrecognised escape at index 1"),
+            (
+                "caf\u{e9}",
+                "Non-ASCII characters are only supported in UTF-8 mode at index 3",
+            ),
+            (
+                "x[a\u{e9}]",
+                "Non-ASCII characters are only supported in UTF-8 mode at index 3",
+            ),
+            (
+                "a\\Qb\u{e9}\\E",
+                "Non-ASCII characters are only supported in UTF-8 mode at index 4",
+            ),
+            (
+                "a\\\u{e9}",
+                "Non-ASCII characters are only supported in UTF-8 mode at index 1",
+            ),
+            (
+                "\\\u{20ac}",
+                "Non-ASCII characters are only supported in UTF-8 mode at index 0",
+            ),
+            (
+                "x[\\\u{e9}]",
+                "Non-ASCII characters are only supported in UTF-8 mode at index 2",
+            ),
+            ("[a-\\d]", "Invalid range in character class at index 3"),
+            (
+                "a\\p{Foo}",
+                "Unknown property name after \\P or \\p at index 1",
+            ),
+            (
+                "\\pZ\\px",
+                "Unknown property name after \\P or \\p at index 3",
+            ),
+            ("[\\P{Greek]", "Malformed \\P or \\p sequence at index 1"),
+            ("\\p", "Malformed \\P or \\p sequence at index 0"),
+            ("[a-\\p{L}]", "Invalid range in character class at index 3"),
+            ("[[.a.]]", "Unsupported POSIX collating element at index 1"),
+            (
+                "a[:alpha:]",
+                "POSIX named classes are only supported inside a class at index 1",
+            ),
+        ] {
+            let mut mode = ParseMode::default();
+            let e = parse(pattern, &mut mode).expect_err("invalid");
+            assert!(matches!(e.kind(), ErrorKind::LocatedParse));
+            assert_eq!(&e.reason, reason);
+        }
+    }
 }
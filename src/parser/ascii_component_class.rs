@@ -1,11 +1,22 @@
-use crate::parser::{ConstComponentVisitor, GlushkovBuildState, ParseMode};
-use crate::util::{make_caseless, CharReach};
+use crate::parser::component_class::{get_predefined_char_reach, PredefinedClass};
+use crate::parser::{ConstComponentVisitor, GlushkovBuildState, ParseMode, Position, PositionInfo};
+use crate::util::compile_error::{CompileError, ErrorKind};
+use crate::util::{make_caseless, CharReach, CodePointSet};
 
 /// Character classes and their mnemonics.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub(crate) struct AsciiComponentClass {
     mode: ParseMode,
     pub(in crate::parser) cr: CharReach,
+    position: Option<Position>,
+
+    /// Whether the class is negated, applied when it is finalized.
+    negate: bool,
+    /// Set after a dash that may turn into a range.
+    in_cand_range: bool,
+    /// Last character added, which may start a range.
+    range_start: Option<u8>,
+    finalized: bool,
 }
 
 impl AsciiComponentClass {
@@ -13,16 +24,141 @@ impl AsciiComponentClass {
         Self {
             mode,
             cr: CharReach::default(),
+            position: None,
+            negate: false,
+            in_cand_range: false,
+            range_start: None,
+            finalized: false,
         }
     }
 
-    pub(in crate::parser) fn add(&mut self, c: u8) {
+    pub(in crate::parser) fn add(&mut self, c: u8) -> Result<(), CompileError> {
+        if self.in_cand_range {
+            return self.create_range(c);
+        }
+
         let mut ncr = CharReach::from_char(c);
         if self.mode.caseless {
             make_caseless(&mut ncr);
         }
 
         self.cr |= ncr;
+        self.range_start = Some(c);
+        Ok(())
+    }
+
+    pub(in crate::parser) fn add_predefined(
+        &mut self,
+        c: PredefinedClass,
+        negated: bool,
+    ) -> Result<(), CompileError> {
+        if self.in_cand_range {
+            // Can't form a range here.
+            return Err(CompileError::new(
+                ErrorKind::LocatedParse,
+                "Invalid range in character class",
+            ));
+        }
+
+        let mut pcr = get_predefined_char_reach(c, self.mode);
+        if negated {
+            pcr.flip_all();
+        }
+
+        self.cr |= pcr;
+        self.range_start = None;
+        Ok(())
+    }
+
+    /// Adds the code points of a Unicode property, of which only those below
+    /// 256 can be matched by a single byte.
+    pub(in crate::parser) fn add_code_points(
+        &mut self,
+        cps: &CodePointSet,
+    ) -> Result<(), CompileError> {
+        if self.in_cand_range {
+            // Can't form a range here.
+            return Err(CompileError::new(
+                ErrorKind::LocatedParse,
+                "Invalid range in character class",
+            ));
+        }
+
+        for &(lo, hi) in cps.ranges() {
+            if lo > 0xff {
+                break;
+            }
+            self.cr.set_range(lo as u8, hi.min(0xff) as u8);
+        }
+        self.range_start = None;
+        Ok(())
+    }
+
+    pub(in crate::parser) fn add_dash(&mut self) -> Result<(), CompileError> {
+        if !self.in_cand_range {
+            // This could be the start of a range.
+            if self.range_start.is_some() {
+                self.in_cand_range = true;
+                Ok(())
+            } else {
+                // No possible start character for range, this is just a
+                // literal.
+                self.add(b'-')
+            }
+        } else {
+            // Already creating a range, so this must be literal '-'.
+            self.create_range(b'-')
+        }
+    }
+
+    fn create_range(&mut self, to: u8) -> Result<(), CompileError> {
+        let from = self.range_start.expect("range must have a start");
+        if from > to {
+            return Err(CompileError::new(
+                ErrorKind::LocatedParse,
+                "Range out of order in character class",
+            ));
+        }
+
+        self.in_cand_range = false;
+        let mut ncr = CharReach::from_range(from, to);
+        if self.mode.caseless {
+            make_caseless(&mut ncr);
+        }
+        self.cr |= ncr;
+        self.range_start = None;
+        Ok(())
+    }
+
+    pub(in crate::parser) fn negate(&mut self) {
+        self.negate = true;
+    }
+
+    pub(in crate::parser) fn finalize(&mut self) {
+        if self.finalized {
+            return;
+        }
+
+        // Handle unclosed ranges, like '[a-]': in these cases the dash is a
+        // literal dash.
+        if self.in_cand_range {
+            self.cr.set(b'-');
+            self.in_cand_range = false;
+        }
+
+        if self.negate {
+            self.cr.flip_all();
+        }
+
+        self.finalized = true;
+    }
+
+    pub(in crate::parser) fn first(&self) -> Vec<PositionInfo> {
+        vec![self.position.expect("positions must be noted first").into()]
+    }
+
+    pub(in crate::parser) fn last(&self) -> Vec<PositionInfo> {
+        self.first()
     }
 
     pub(in crate::parser) fn note_positions(&mut self, bs: &mut GlushkovBuildState) {
@@ -30,6 +166,15 @@ impl AsciiComponentClass {
         let position = builder.make_position();
 
         builder.add_char_reach(position, self.cr);
+        self.position = Some(position);
+    }
+
+    pub(in crate::parser) fn build_follow_set(
+        &self,
+        bs: &mut GlushkovBuildState,
+        last_pos: &[PositionInfo],
+    ) {
+        bs.connect_regions(last_pos, &self.first());
     }
 }
 
@@ -2,6 +2,7 @@ use std::collections::{HashMap, HashSet};
 
 use crate::nfagraph::{NfaBuilder, NfaVertex};
 use crate::parser::{PosFlags, Position, PositionInfo};
+use crate::util::CharReach;
 
 pub(crate) struct GlushkovBuildState<'a> {
     start_state: Position,
@@ -13,6 +14,10 @@ pub(crate) struct GlushkovBuildState<'a> {
 
     /// Map storing successors for each position.
     successors: HashMap<Position, HashSet<PositionInfo>>,
+
+    /// Newline positions matching just before the accept state they lead to,
+    /// keyed by that accept state.
+    newlines: HashMap<Position, Position>,
 }
 
 impl<'a> GlushkovBuildState<'a> {
@@ -29,6 +34,7 @@ impl<'a> GlushkovBuildState<'a> {
             accept_eod_state,
             builder: b,
             successors: HashMap::default(),
+            newlines: HashMap::default(),
         };
 
         let lasts: Vec<PositionInfo> = vec![start_state.into(), start_dot_star_state.into()];
@@ -38,8 +44,7 @@ impl<'a> GlushkovBuildState<'a> {
         bs
     }
 
-    #[allow(dead_code)]
-    pub(in crate::parser) fn get_builder(&self) -> &NfaBuilder {
+    pub(crate) fn get_builder(&self) -> &NfaBuilder {
         self.builder
     }
 
@@ -47,35 +52,162 @@ impl<'a> GlushkovBuildState<'a> {
         self.builder
     }
 
+    /// Returns the positions every expression is wired from: the start
+    /// dot-star state and the (anchored) start state.
+    pub(crate) fn initials(&self) -> Vec<PositionInfo> {
+        vec![self.start_dot_star_state.into(), self.start_state.into()]
+    }
+
     pub(crate) fn connect_regions(&mut self, lasts: &[PositionInfo], firsts: &[PositionInfo]) {
         for last in lasts {
             self.connect_successors(*last, firsts.to_vec());
         }
     }
 
+    /// Wires the given positions to the accept states, as their end anchor
+    /// flags require.
+    pub(crate) fn connect_accepts(&mut self, lasts: &[PositionInfo]) {
+        for last in lasts.iter().filter(|p| !is_epsilon(p)) {
+            let flags = last.flags;
+            if !flags.contains(PosFlags::ONLY_ENDS) {
+                let accept = self.accept_state.into();
+                self.connect_successors(*last, vec![accept]);
+            }
+            if flags.contains(PosFlags::WIRE_EOD) {
+                let accept_eod = self.accept_eod_state.into();
+                self.connect_successors(*last, vec![accept_eod]);
+            }
+            let nl_accept =
+                flags.contains(PosFlags::WIRE_NL_ACCEPT) && !flags.contains(PosFlags::NO_NL_ACCEPT);
+            if nl_accept {
+                let newline = self.newline_before(self.accept_state).into();
+                self.connect_successors(*last, vec![newline]);
+            } else if flags.contains(PosFlags::WIRE_NL_EOD) && !flags.contains(PosFlags::NO_NL_EOD)
+            {
+                // A newline wired to accept already covers one ending the data.
+                let newline = self.newline_before(self.accept_eod_state).into();
+                self.connect_successors(*last, vec![newline]);
+            }
+        }
+    }
+
+    /// Returns a newline position wired to `accept` that reports the offset
+    /// before the newline, creating it if needed.
+    fn newline_before(&mut self, accept: Position) -> Position {
+        if let Some(&newline) = self.newlines.get(&accept) {
+            return newline;
+        }
+        let newline = self.builder.make_position();
+        self.builder
+            .add_char_reach(newline, CharReach::from_char(b'\n'));
+        self.builder.set_node_report_id(newline, -1);
+        self.successors
+            .entry(newline)
+            .or_default()
+            .insert(accept.into());
+        self.newlines.insert(accept, newline);
+        newline
+    }
+
     fn connect_successors(&mut self, from: PositionInfo, mut tolist: Vec<PositionInfo>) {
-        debug_assert!(from.pos != GlushkovBuildState::pos_epsilon());
+        debug_assert!(!is_epsilon(&from));
 
         filter_edges(self, from, &mut tolist);
 
-        let i = tolist.iter().position(|e| e.pos == self.accept_state);
-        if let Some(i) = i {
-            let fakedot = self.builder.make_position();
-            tolist[i] = fakedot.into();
-        }
+        // Epsilons are placeholders for "whatever follows"; they never become
+        // edges themselves.
+        tolist.retain(|e| !is_epsilon(e));
 
-        let succ = self.successors.entry(from.pos).or_insert_with(HashSet::new);
+        let succ = self.successors.entry(from.pos).or_default();
 
+        // Word boundary assertions on either side end up on the edge, which
+        // can only be taken if they all hold.
+        let asserts = from.flags & PosFlags::ALL_WORD_ASSERTS;
         for to in &tolist {
-            succ.insert(*to);
+            if let Some(flags) = to.flags.chain(asserts) {
+                succ.insert(PositionInfo::with_flags(to.pos, flags));
+            }
         }
     }
 
-    fn pos_epsilon() -> Position {
+    /// Turns the successor sets collected so far into edges of the graph, and
+    /// attaches a report to every position wired to an accept state.
+    pub(crate) fn build_edges(&mut self) {
+        let mut successors: Vec<_> = self.successors.drain().collect();
+        successors.sort_by_key(|(from, _)| *from);
+        let newlines: HashSet<Position> = self.newlines.values().copied().collect();
+        for (from, tos) in successors {
+            let is_newline = newlines.contains(&from);
+            // An edge is only subject to assertions if every way of making it
+            // is, and then to those of any of them.
+            let mut edges: HashMap<Position, Option<PosFlags>> = HashMap::new();
+            for to in tos {
+                let asserts = to.flags & PosFlags::ALL_WORD_ASSERTS;
+                let flags = edges.entry(to.pos).or_insert(Some(PosFlags::empty()));
+                *flags = match *flags {
+                    Some(f) if !asserts.is_empty() => Some(f | asserts),
+                    _ => None,
+                };
+            }
+            let mut edges: Vec<_> = edges
+                .into_iter()
+                .map(|(to, flags)| (to, flags.unwrap_or_default()))
+                .collect();
+            edges.sort_by_key(|(to, _)| *to);
+            for (to, assert_flags) in edges {
+                if (to == self.accept_state || to == self.accept_eod_state) && !is_newline {
+                    self.builder.set_node_report_id(from, 0);
+                }
+                self.builder.add_edge(from, to, assert_flags);
+            }
+        }
+    }
+
+    pub(in crate::parser) fn pos_epsilon() -> Position {
         Position::new(NfaVertex::end().index() - 1)
     }
 }
 
+pub(in crate::parser) fn is_epsilon(p: &PositionInfo) -> bool {
+    p.pos == GlushkovBuildState::pos_epsilon()
+}
+
+/// Returns an epsilon position carrying the given flags.
+pub(in crate::parser) fn epsilon(flags: PosFlags) -> PositionInfo {
+    PositionInfo::with_flags(GlushkovBuildState::pos_epsilon(), flags)
+}
+
+/// Replaces every epsilon in `target` with the positions in `source`, carrying
+/// the flags of the epsilon over to each of them. Positions whose assertions
+/// cannot all hold are dropped.
+pub(crate) fn replace_epsilons(target: &mut Vec<PositionInfo>, source: &[PositionInfo]) {
+    let eps_flags: Vec<PosFlags> = target
+        .iter()
+        .filter(|p| is_epsilon(p))
+        .map(|p| p.flags)
+        .collect();
+    if eps_flags.is_empty() {
+        return;
+    }
+    target.retain(|p| !is_epsilon(p));
+    for flags in eps_flags {
+        for s in source {
+            let p = match s.flags.chain(flags) {
+                Some(flags) => PositionInfo::with_flags(s.pos, flags),
+                None => continue,
+            };
+            if !target.contains(&p) {
+                target.push(p);
+            }
+        }
+    }
+}
+
+/// Returns `true` if any of the given positions is an epsilon.
+pub(in crate::parser) fn has_epsilon(positions: &[PositionInfo]) -> bool {
+    positions.iter().any(is_epsilon)
+}
+
 fn filter_edges(bs: &GlushkovBuildState, from: PositionInfo, tolist: &mut Vec<PositionInfo>) {
     if from.pos == bs.start_dot_star_state {
         // If we're connecting from start-dotstar, remove all caret flavored
@@ -108,6 +240,6 @@ fn filter_edges(bs: &GlushkovBuildState, from: PositionInfo, tolist: &mut Vec<Po
     }
 }
 
-pub(crate) fn make_glushkov_build_state(b: &mut NfaBuilder) -> GlushkovBuildState {
+pub(crate) fn make_glushkov_build_state(b: &mut NfaBuilder) -> GlushkovBuildState<'_> {
     GlushkovBuildState::new(b)
 }
@@ -0,0 +1,101 @@
+use super::ascii_component_class::AsciiComponentClass;
+use super::utf8_component_class::Utf8ComponentClass;
+use super::{
+    walk_component, Component, ComponentAlternation, ComponentAssertion, ComponentAtomicGroup,
+    ComponentBackReference, ComponentBoundary, ComponentCondReference, ComponentRepeat,
+    ComponentSequence, ComponentWordBoundary, ConstComponentVisitor,
+};
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+
+/// Visitor collecting the names of named capturing groups.
+#[derive(Default)]
+struct CaptureNamesVisitor {
+    names: BTreeMap<String, u32>,
+}
+
+impl ConstComponentVisitor for CaptureNamesVisitor {
+    type Error = Infallible;
+
+    fn pre_component_sequence(&mut self, c: &ComponentSequence) -> Result<(), Self::Error> {
+        if let (Some(name), Some(index)) = (&c.capture_name, c.capture_index) {
+            self.names.insert(name.clone(), index);
+        }
+        Ok(())
+    }
+
+    fn pre_ascii_component_class(&mut self, _c: &AsciiComponentClass) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn pre_component_alternation(&mut self, _c: &ComponentAlternation) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn pre_component_boundary(&mut self, _c: &ComponentBoundary) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn pre_component_repeat(&mut self, _c: &ComponentRepeat) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn pre_utf8_component_class(&mut self, _c: &Utf8ComponentClass) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn pre_component_word_boundary(
+        &mut self,
+        _c: &ComponentWordBoundary,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn pre_component_assertion(&mut self, _c: &ComponentAssertion) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn pre_component_atomic_group(&mut self, _c: &ComponentAtomicGroup) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn pre_component_back_reference(
+        &mut self,
+        _c: &ComponentBackReference,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn pre_component_cond_reference(
+        &mut self,
+        _c: &ComponentCondReference,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn during_ascii_component_class(&self, _c: &AsciiComponentClass) {}
+    fn during_component_alternation(&self, _c: &ComponentAlternation) {}
+    fn during_component_sequence(&self, _c: &ComponentSequence) {}
+    fn during_utf8_component_class(&self, _c: &Utf8ComponentClass) {}
+
+    fn post_ascii_component_class(&mut self, _c: &AsciiComponentClass) {}
+    fn post_component_alternation(&mut self, _c: &ComponentAlternation) {}
+    fn post_component_boundary(&mut self, _c: &ComponentBoundary) {}
+    fn post_component_repeat(&mut self, _c: &ComponentRepeat) {}
+    fn post_component_sequence(&mut self, _c: &ComponentSequence) {}
+    fn post_utf8_component_class(&mut self, _c: &Utf8ComponentClass) {}
+    fn post_component_assertion(&mut self, _c: &ComponentAssertion) {}
+    fn post_component_atomic_group(&mut self, _c: &ComponentAtomicGroup) {}
+    fn post_component_back_reference(&mut self, _c: &ComponentBackReference) {}
+    fn post_component_cond_reference(&mut self, _c: &ComponentCondReference) {}
+    fn post_component_word_boundary(&mut self, _c: &ComponentWordBoundary) {}
+}
+
+/// Returns the index of each named capturing group in the tree, by name.
+pub(crate) fn capture_names(root: &Component) -> BTreeMap<String, u32> {
+    let mut vis = CaptureNamesVisitor::default();
+    match walk_component(&mut vis, root) {
+        Ok(()) => vis.names,
+        Err(never) => match never {},
+    }
+}
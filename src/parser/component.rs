@@ -1,40 +1,209 @@
 use super::component_alternation::walk_component_alternation;
+use super::component_assertion::walk_component_assertion;
+use super::component_atomic_group::walk_component_atomic_group;
+use super::component_back_reference::walk_component_back_reference;
+use super::component_boundary::walk_component_boundary;
 use super::component_class::walk_component_class;
+use super::component_cond_reference::walk_component_cond_reference;
+use super::component_repeat::walk_component_repeat;
 use super::component_sequence::walk_component_sequence;
-use super::GlushkovBuildState;
-use super::{ComponentAlternation, ComponentClass, ComponentSequence, ConstComponentVisitor};
+use super::component_word_boundary::walk_component_word_boundary;
+use super::{
+    ComponentAlternation, ComponentAssertion, ComponentAtomicGroup, ComponentBackReference,
+    ComponentBoundary, ComponentClass, ComponentCondReference, ComponentRepeat, ComponentSequence,
+    ComponentWordBoundary, ConstComponentVisitor,
+};
+use super::{GlushkovBuildState, PositionInfo};
+use crate::util::compile_error::CompileError;
 
 /// A component for a regular expression parse tree.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub(crate) enum Component {
     Alternation(ComponentAlternation),
+    Assertion(ComponentAssertion),
+    AtomicGroup(ComponentAtomicGroup),
+    BackReference(ComponentBackReference),
+    Boundary(ComponentBoundary),
     Class(ComponentClass),
+    CondReference(ComponentCondReference),
+    Repeat(ComponentRepeat),
     Sequence(ComponentSequence),
+    WordBoundary(ComponentWordBoundary),
 }
 
 impl Component {
+    /// Returns the positions this component may start with.
+    ///
+    /// An epsilon position is included if the component can match the empty
+    /// string.
+    pub(crate) fn first(&self) -> Vec<PositionInfo> {
+        match self {
+            Self::Alternation(c) => c.first(),
+            Self::Boundary(c) => c.first(),
+            Self::Class(c) => c.first(),
+            Self::Repeat(c) => c.first(),
+            Self::Sequence(c) => c.first(),
+            Self::WordBoundary(c) => c.first(),
+            Self::Assertion(_)
+            | Self::AtomicGroup(_)
+            | Self::BackReference(_)
+            | Self::CondReference(_) => unreachable!("unsupported components are rejected"),
+        }
+    }
+
+    /// Returns the positions this component may end with.
+    ///
+    /// An epsilon position is included if the component can match the empty
+    /// string.
+    pub(crate) fn last(&self) -> Vec<PositionInfo> {
+        match self {
+            Self::Alternation(c) => c.last(),
+            Self::Boundary(c) => c.last(),
+            Self::Class(c) => c.last(),
+            Self::Repeat(c) => c.last(),
+            Self::Sequence(c) => c.last(),
+            Self::WordBoundary(c) => c.last(),
+            Self::Assertion(_)
+            | Self::AtomicGroup(_)
+            | Self::BackReference(_)
+            | Self::CondReference(_) => unreachable!("unsupported components are rejected"),
+        }
+    }
+
     /// Informs the Glushkov build process of the positions used by this component.
     pub(crate) fn note_positions(&mut self, bs: &mut GlushkovBuildState) {
         match self {
             Self::Alternation(c) => c.note_positions(bs),
+            Self::Boundary(c) => c.note_positions(bs),
             Self::Class(c) => c.note_positions(bs),
+            Self::Repeat(c) => c.note_positions(bs),
             Self::Sequence(c) => c.note_positions(bs),
+            Self::WordBoundary(_) => {}
+            Self::Assertion(_)
+            | Self::AtomicGroup(_)
+            | Self::BackReference(_)
+            | Self::CondReference(_) => unreachable!("unsupported components are rejected"),
+        }
+    }
+
+    /// Wires `last_pos`, the positions preceding this component, to its first
+    /// positions and builds the follow set of its internal positions.
+    pub(crate) fn build_follow_set(&self, bs: &mut GlushkovBuildState, last_pos: &[PositionInfo]) {
+        match self {
+            Self::Alternation(c) => c.build_follow_set(bs, last_pos),
+            Self::Boundary(c) => c.build_follow_set(bs, last_pos),
+            Self::Class(c) => c.build_follow_set(bs, last_pos),
+            Self::Repeat(c) => c.build_follow_set(bs, last_pos),
+            Self::Sequence(c) => c.build_follow_set(bs, last_pos),
+            Self::WordBoundary(_) => {}
+            Self::Assertion(_)
+            | Self::AtomicGroup(_)
+            | Self::BackReference(_)
+            | Self::CondReference(_) => unreachable!("unsupported components are rejected"),
+        }
+    }
+
+    /// Returns `true` if a quantifier may be applied to this component.
+    pub(crate) fn repeatable(&self) -> bool {
+        !matches!(
+            self,
+            Self::Boundary(_) | Self::Repeat(_) | Self::WordBoundary(_)
+        )
+    }
+
+    /// Checks that start anchors only appear where nothing may have been
+    /// matched before them, given whether that holds on entry.
+    ///
+    /// Returns whether it still holds after this component.
+    pub(crate) fn check_embedded_start_anchor(&self, at_start: bool) -> Result<bool, CompileError> {
+        match self {
+            Self::Alternation(c) => c.check_embedded_start_anchor(at_start),
+            Self::Assertion(c) => c.check_embedded_start_anchor(at_start),
+            Self::AtomicGroup(c) => c.seq.check_embedded_start_anchor(at_start),
+            Self::BackReference(_) => Ok(false),
+            Self::Boundary(c) => c.check_embedded_start_anchor(at_start),
+            Self::Class(_) => Ok(false),
+            Self::CondReference(c) => c.seq.check_embedded_start_anchor(at_start),
+            Self::Repeat(c) => c.check_embedded_start_anchor(at_start),
+            Self::Sequence(c) => c.check_embedded_start_anchor(at_start),
+            Self::WordBoundary(_) => Ok(at_start),
+        }
+    }
+
+    /// Checks that end anchors only appear where nothing may be matched after
+    /// them, given whether that holds on exit.
+    ///
+    /// Returns whether it holds before this component.
+    pub(crate) fn check_embedded_end_anchor(&self, at_end: bool) -> Result<bool, CompileError> {
+        match self {
+            Self::Alternation(c) => c.check_embedded_end_anchor(at_end),
+            Self::Assertion(c) => c.check_embedded_end_anchor(at_end),
+            Self::AtomicGroup(c) => c.seq.check_embedded_end_anchor(at_end),
+            Self::BackReference(_) => Ok(false),
+            Self::Boundary(c) => c.check_embedded_end_anchor(at_end),
+            Self::Class(_) => Ok(false),
+            Self::CondReference(c) => c.seq.check_embedded_end_anchor(at_end),
+            Self::Repeat(c) => c.check_embedded_end_anchor(at_end),
+            Self::Sequence(c) => c.check_embedded_end_anchor(at_end),
+            Self::WordBoundary(_) => Ok(at_end),
         }
     }
 }
 
+impl From<ComponentAssertion> for Component {
+    fn from(c: ComponentAssertion) -> Self {
+        Self::Assertion(c)
+    }
+}
+
+impl From<ComponentAtomicGroup> for Component {
+    fn from(c: ComponentAtomicGroup) -> Self {
+        Self::AtomicGroup(c)
+    }
+}
+
+impl From<ComponentBackReference> for Component {
+    fn from(c: ComponentBackReference) -> Self {
+        Self::BackReference(c)
+    }
+}
+
+impl From<ComponentBoundary> for Component {
+    fn from(c: ComponentBoundary) -> Self {
+        Self::Boundary(c)
+    }
+}
+
 impl From<ComponentClass> for Component {
     fn from(c: ComponentClass) -> Self {
         Self::Class(c)
     }
 }
 
+impl From<ComponentCondReference> for Component {
+    fn from(c: ComponentCondReference) -> Self {
+        Self::CondReference(c)
+    }
+}
+
+impl From<ComponentRepeat> for Component {
+    fn from(c: ComponentRepeat) -> Self {
+        Self::Repeat(c)
+    }
+}
+
 impl From<ComponentSequence> for Component {
     fn from(c: ComponentSequence) -> Self {
         Self::Sequence(c)
     }
 }
 
+impl From<ComponentWordBoundary> for Component {
+    fn from(c: ComponentWordBoundary) -> Self {
+        Self::WordBoundary(c)
+    }
+}
+
 /// Applies the given const visitor functor.
 pub(crate) fn walk_component<V: ConstComponentVisitor>(
     v: &mut V,
@@ -42,7 +211,14 @@ pub(crate) fn walk_component<V: ConstComponentVisitor>(
 ) -> Result<(), V::Error> {
     match c {
         Component::Alternation(c) => walk_component_alternation(v, c),
+        Component::Assertion(c) => walk_component_assertion(v, c),
+        Component::AtomicGroup(c) => walk_component_atomic_group(v, c),
+        Component::BackReference(c) => walk_component_back_reference(v, c),
+        Component::Boundary(c) => walk_component_boundary(v, c),
         Component::Class(c) => walk_component_class(v, c),
+        Component::CondReference(c) => walk_component_cond_reference(v, c),
+        Component::Repeat(c) => walk_component_repeat(v, c),
         Component::Sequence(c) => walk_component_sequence(v, c),
+        Component::WordBoundary(c) => walk_component_word_boundary(v, c),
     }
 }
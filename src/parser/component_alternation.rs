@@ -1,7 +1,8 @@
 use super::component_sequence::walk_component_sequence;
-use super::{ComponentSequence, ConstComponentVisitor, GlushkovBuildState};
+use super::{ComponentSequence, ConstComponentVisitor, GlushkovBuildState, PositionInfo};
+use crate::util::compile_error::CompileError;
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub(crate) struct ComponentAlternation {
     children: Vec<ComponentSequence>,
 }
@@ -11,12 +12,60 @@ impl ComponentAlternation {
         self.children.push(component);
     }
 
+    pub(super) fn num_branches(&self) -> usize {
+        self.children.len()
+    }
+
+    pub(super) fn children_mut(&mut self) -> &mut Vec<ComponentSequence> {
+        &mut self.children
+    }
+
+    pub(super) fn first(&self) -> Vec<PositionInfo> {
+        union(self.children.iter().map(ComponentSequence::first))
+    }
+
+    pub(super) fn last(&self) -> Vec<PositionInfo> {
+        union(self.children.iter().map(ComponentSequence::last))
+    }
+
     /// Informs the Glushkov build process of the positions used by this component.
     pub(super) fn note_positions(&mut self, bs: &mut GlushkovBuildState) {
         for c in &mut self.children {
             c.note_positions(bs);
         }
     }
+
+    pub(super) fn build_follow_set(&self, bs: &mut GlushkovBuildState, last_pos: &[PositionInfo]) {
+        for c in &self.children {
+            c.build_follow_set(bs, last_pos);
+        }
+    }
+
+    pub(super) fn check_embedded_start_anchor(&self, at_start: bool) -> Result<bool, CompileError> {
+        let mut rv = at_start;
+        for c in &self.children {
+            rv &= c.check_embedded_start_anchor(at_start)?;
+        }
+        Ok(rv)
+    }
+
+    pub(super) fn check_embedded_end_anchor(&self, at_end: bool) -> Result<bool, CompileError> {
+        let mut rv = at_end;
+        for c in &self.children {
+            rv &= c.check_embedded_end_anchor(at_end)?;
+        }
+        Ok(rv)
+    }
+}
+
+fn union<I: Iterator<Item = Vec<PositionInfo>>>(sets: I) -> Vec<PositionInfo> {
+    let mut out = Vec::new();
+    for p in sets.flatten() {
+        if !out.contains(&p) {
+            out.push(p);
+        }
+    }
+    out
 }
 
 pub(super) fn walk_component_alternation<V: ConstComponentVisitor>(
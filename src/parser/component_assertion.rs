@@ -0,0 +1,67 @@
+use super::component_sequence::walk_component_sequence;
+use super::{ComponentSequence, ConstComponentVisitor};
+use crate::util::compile_error::CompileError;
+
+/// Direction in which an assertion looks.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum AssertionDir {
+    Lookahead,
+    Lookbehind,
+}
+
+/// A lookahead or lookbehind assertion, such as `(?=...)` or `(?<!...)`.
+///
+/// This is parsed so that it can be reported, or dropped in prefilter mode,
+/// but cannot be compiled.
+#[derive(Clone, Debug)]
+pub(crate) struct ComponentAssertion {
+    pub(in crate::parser) dir: AssertionDir,
+    #[allow(dead_code)]
+    pub(in crate::parser) negated: bool,
+    pub(in crate::parser) seq: ComponentSequence,
+    /// Offset of the assertion in the pattern.
+    pub(in crate::parser) loc: usize,
+}
+
+impl ComponentAssertion {
+    pub(in crate::parser) fn new(
+        dir: AssertionDir,
+        negated: bool,
+        seq: ComponentSequence,
+        loc: usize,
+    ) -> Self {
+        Self {
+            dir,
+            negated,
+            seq,
+            loc,
+        }
+    }
+
+    /// Checks the anchors inside the assertion, which matches nothing itself.
+    pub(in crate::parser) fn check_embedded_start_anchor(
+        &self,
+        at_start: bool,
+    ) -> Result<bool, CompileError> {
+        self.seq.check_embedded_start_anchor(at_start)?;
+        Ok(at_start)
+    }
+
+    pub(in crate::parser) fn check_embedded_end_anchor(
+        &self,
+        at_end: bool,
+    ) -> Result<bool, CompileError> {
+        self.seq.check_embedded_end_anchor(at_end)?;
+        Ok(at_end)
+    }
+}
+
+pub(in crate::parser) fn walk_component_assertion<V: ConstComponentVisitor>(
+    v: &mut V,
+    c: &ComponentAssertion,
+) -> Result<(), V::Error> {
+    v.pre_component_assertion(c)?;
+    walk_component_sequence(v, &c.seq)?;
+    v.post_component_assertion(c);
+    Ok(())
+}
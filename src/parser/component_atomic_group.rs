@@ -0,0 +1,29 @@
+use super::component_sequence::walk_component_sequence;
+use super::{ComponentSequence, ConstComponentVisitor};
+
+/// An atomic group, `(?>...)`.
+///
+/// This is parsed so that it can be reported, or matched as a plain group in
+/// prefilter mode, but cannot be compiled.
+#[derive(Clone, Debug)]
+pub(crate) struct ComponentAtomicGroup {
+    pub(in crate::parser) seq: ComponentSequence,
+    /// Offset of the group in the pattern.
+    pub(in crate::parser) loc: usize,
+}
+
+impl ComponentAtomicGroup {
+    pub(in crate::parser) fn new(seq: ComponentSequence, loc: usize) -> Self {
+        Self { seq, loc }
+    }
+}
+
+pub(in crate::parser) fn walk_component_atomic_group<V: ConstComponentVisitor>(
+    v: &mut V,
+    c: &ComponentAtomicGroup,
+) -> Result<(), V::Error> {
+    v.pre_component_atomic_group(c)?;
+    walk_component_sequence(v, &c.seq)?;
+    v.post_component_atomic_group(c);
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use super::ConstComponentVisitor;
+
+/// The group a backreference refers to.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum BackRef {
+    Index(u32),
+    Name(String),
+}
+
+/// A backreference to the text matched by a capturing group.
+///
+/// This is parsed so that it can be reported, or approximated in prefilter
+/// mode, but cannot be compiled.
+#[derive(Clone, Debug)]
+pub(crate) struct ComponentBackReference {
+    #[allow(dead_code)]
+    pub(in crate::parser) reference: BackRef,
+    /// Offset of the backreference in the pattern.
+    pub(in crate::parser) loc: usize,
+}
+
+impl ComponentBackReference {
+    pub(in crate::parser) fn new(reference: BackRef, loc: usize) -> Self {
+        Self { reference, loc }
+    }
+}
+
+pub(in crate::parser) fn walk_component_back_reference<V: ConstComponentVisitor>(
+    v: &mut V,
+    c: &ComponentBackReference,
+) -> Result<(), V::Error> {
+    v.pre_component_back_reference(c)?;
+    v.post_component_back_reference(c);
+    Ok(())
+}
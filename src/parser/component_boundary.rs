@@ -0,0 +1,143 @@
+use super::build_state::epsilon;
+use super::{ConstComponentVisitor, GlushkovBuildState, PosFlags, Position, PositionInfo};
+use crate::util::compile_error::{CompileError, ErrorKind};
+use crate::util::CharReach;
+
+/// Kind of an anchor.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Boundary {
+    /// Beginning of data (`^`, `\A`).
+    BeginString,
+    /// End of data (`\z`).
+    EndString,
+    /// End of data, or before a newline ending it (`$`, `\Z`).
+    EndStringOptionalLf,
+    /// Beginning of data or after a newline (multiline `^`).
+    BeginLine,
+    /// End of data or before a newline (multiline `$`).
+    EndLine,
+}
+
+/// A zero-width anchor.
+#[derive(Clone, Debug)]
+pub(crate) struct ComponentBoundary {
+    pub(in crate::parser) bound: Boundary,
+
+    /// Newline position preceding a multiline `^`, once positions are noted.
+    newline: Option<Position>,
+}
+
+impl ComponentBoundary {
+    pub(in crate::parser) fn new(bound: Boundary) -> Self {
+        Self {
+            bound,
+            newline: None,
+        }
+    }
+
+    fn is_start(&self) -> bool {
+        matches!(self.bound, Boundary::BeginString | Boundary::BeginLine)
+    }
+
+    /// Returns the flags carried over to the positions around the anchor.
+    fn flags(&self) -> PosFlags {
+        match self.bound {
+            Boundary::BeginString | Boundary::BeginLine => PosFlags::NOFLOAT,
+            Boundary::EndString => {
+                PosFlags::WIRE_EOD
+                    | PosFlags::NO_NL_EOD
+                    | PosFlags::NO_NL_ACCEPT
+                    | PosFlags::ONLY_ENDS
+            }
+            Boundary::EndStringOptionalLf => {
+                PosFlags::WIRE_EOD
+                    | PosFlags::WIRE_NL_EOD
+                    | PosFlags::NO_NL_ACCEPT
+                    | PosFlags::ONLY_ENDS
+            }
+            Boundary::EndLine => {
+                PosFlags::WIRE_EOD
+                    | PosFlags::WIRE_NL_EOD
+                    | PosFlags::WIRE_NL_ACCEPT
+                    | PosFlags::ONLY_ENDS
+            }
+        }
+    }
+
+    pub(in crate::parser) fn first(&self) -> Vec<PositionInfo> {
+        let mut firsts = vec![epsilon(self.flags())];
+        if let Some(newline) = self.newline {
+            firsts.push(PositionInfo::with_flags(newline, PosFlags::MUST_FLOAT));
+        }
+        firsts
+    }
+
+    pub(in crate::parser) fn last(&self) -> Vec<PositionInfo> {
+        let mut lasts = vec![epsilon(self.flags())];
+        if let Some(newline) = self.newline {
+            lasts.push(newline.into());
+        }
+        lasts
+    }
+
+    /// Informs the Glushkov build process of the positions used by this component.
+    pub(in crate::parser) fn note_positions(&mut self, bs: &mut GlushkovBuildState) {
+        if self.bound == Boundary::BeginLine {
+            // A multiline caret floats on the newline before it, which is not
+            // part of the match.
+            let builder = bs.get_builder_mut();
+            let newline = builder.make_position();
+            builder.add_char_reach(newline, CharReach::from_char(b'\n'));
+            builder.set_assert_flag(newline, PosFlags::MULTILINE_START);
+            self.newline = Some(newline);
+        }
+    }
+
+    pub(in crate::parser) fn build_follow_set(
+        &self,
+        bs: &mut GlushkovBuildState,
+        last_pos: &[PositionInfo],
+    ) {
+        if let Some(newline) = self.newline {
+            bs.connect_regions(
+                last_pos,
+                &[PositionInfo::with_flags(newline, PosFlags::MUST_FLOAT)],
+            );
+        }
+    }
+
+    pub(in crate::parser) fn check_embedded_start_anchor(
+        &self,
+        at_start: bool,
+    ) -> Result<bool, CompileError> {
+        if !at_start && self.is_start() {
+            return Err(CompileError::new(
+                ErrorKind::Parse,
+                "Embedded start anchors not supported.",
+            ));
+        }
+        Ok(at_start)
+    }
+
+    pub(in crate::parser) fn check_embedded_end_anchor(
+        &self,
+        at_end: bool,
+    ) -> Result<bool, CompileError> {
+        if !at_end && !self.is_start() {
+            return Err(CompileError::new(
+                ErrorKind::Parse,
+                "Embedded end anchors not supported.",
+            ));
+        }
+        Ok(at_end)
+    }
+}
+
+pub(in crate::parser) fn walk_component_boundary<V: ConstComponentVisitor>(
+    v: &mut V,
+    c: &ComponentBoundary,
+) -> Result<(), V::Error> {
+    v.pre_component_boundary(c)?;
+    v.post_component_boundary(c);
+    Ok(())
+}
@@ -1,9 +1,89 @@
 use super::ascii_component_class::{walk_ascii_component_class, AsciiComponentClass};
-use super::{ConstComponentVisitor, GlushkovBuildState, ParseMode};
+use super::utf8_component_class::{walk_utf8_component_class, Utf8ComponentClass};
+use super::{ConstComponentVisitor, GlushkovBuildState, ParseMode, PositionInfo};
 use crate::util::compile_error::{CompileError, ErrorKind};
+use crate::util::{CharReach, CodePointSet};
+
+/// Named classes of characters, as used in POSIX bracket expressions.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(in crate::parser) enum PredefinedClass {
+    Alnum,
+    Alpha,
+    Any,
+    Ascii,
+    Blank,
+    Cntrl,
+    Digit,
+    Graph,
+    Horz,
+    Lower,
+    Print,
+    Punct,
+    Space,
+    Upper,
+    Vert,
+    Word,
+    Xdigit,
+}
+
+/// Returns the characters in the given class.
+pub(in crate::parser) fn get_predefined_char_reach(
+    c: PredefinedClass,
+    mode: ParseMode,
+) -> CharReach {
+    let lower = CharReach::from_range(b'a', b'z');
+    let upper = CharReach::from_range(b'A', b'Z');
+    let number = CharReach::from_range(b'0', b'9');
+    match c {
+        PredefinedClass::Alnum => lower | upper | number,
+        PredefinedClass::Alpha => lower | upper,
+        PredefinedClass::Any => {
+            if mode.dotall {
+                CharReach::dot()
+            } else {
+                !CharReach::from_char(b'\n')
+            }
+        }
+        PredefinedClass::Ascii => CharReach::from_range(0, 127),
+        PredefinedClass::Blank => CharReach::from_bytes(b" \t"),
+        PredefinedClass::Cntrl => CharReach::from_range(0, 31) | CharReach::from_char(127),
+        PredefinedClass::Digit => number,
+        PredefinedClass::Graph => CharReach::from_range(0x21, 0x7e),
+        PredefinedClass::Horz => CharReach::from_bytes(b"\x09\x20\xa0"),
+        PredefinedClass::Lower => {
+            if mode.caseless {
+                lower | upper
+            } else {
+                lower
+            }
+        }
+        PredefinedClass::Print => CharReach::from_range(0x20, 0x7e),
+        PredefinedClass::Punct => {
+            CharReach::from_range(0x21, b'0' - 1)
+                | CharReach::from_range(b'9' + 1, b'A' - 1)
+                | CharReach::from_range(b'Z' + 1, b'a' - 1)
+                | CharReach::from_range(b'z' + 1, 126)
+        }
+        PredefinedClass::Space => CharReach::from_bytes(b"\x09\x0a\x0c\x0b\x0d\x20"),
+        PredefinedClass::Upper => {
+            if mode.caseless {
+                lower | upper
+            } else {
+                upper
+            }
+        }
+        PredefinedClass::Vert => CharReach::from_bytes(b"\x0a\x0b\x0c\x0d\x85"),
+        PredefinedClass::Word => lower | upper | number | CharReach::from_char(b'_'),
+        PredefinedClass::Xdigit => CharReach::from_bytes(b"0123456789abcdefABCDEF"),
+    }
+}
 
 pub(in crate::parser) fn get_component_class(mode: ParseMode) -> ComponentClass {
-    ComponentClass::Ascii(AsciiComponentClass::new(mode))
+    if mode.utf8 {
+        ComponentClass::Utf8(Utf8ComponentClass::new(mode))
+    } else {
+        ComponentClass::Ascii(AsciiComponentClass::new(mode))
+    }
 }
 
 /// Generates a component for a single literal character, possibly in caseless
@@ -18,16 +98,24 @@ pub(in crate::parser) fn get_literal_component_class(
     };
     let mut cc = get_component_class(mode);
     cc.add(c)?;
+    cc.finalize();
     Ok(cc)
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub(crate) enum ComponentClass {
     Ascii(AsciiComponentClass),
+    Utf8(Utf8ComponentClass),
+}
+
+impl From<AsciiComponentClass> for ComponentClass {
+    fn from(c: AsciiComponentClass) -> Self {
+        Self::Ascii(c)
+    }
 }
 
 impl ComponentClass {
-    fn add(&mut self, c: char) -> Result<(), CompileError> {
+    pub(in crate::parser) fn add(&mut self, c: char) -> Result<(), CompileError> {
         match self {
             Self::Ascii(cc) => {
                 if c > '\u{ff}' {
@@ -38,14 +126,85 @@ impl ComponentClass {
                 }
                 cc.add(c as u8)
             }
+            Self::Utf8(cc) => cc.add(c),
+        }
+    }
+
+    pub(in crate::parser) fn add_predefined(
+        &mut self,
+        c: PredefinedClass,
+        negated: bool,
+    ) -> Result<(), CompileError> {
+        match self {
+            Self::Ascii(cc) => cc.add_predefined(c, negated),
+            Self::Utf8(cc) => cc.add_predefined(c, negated),
+        }
+    }
+
+    /// Adds the code points of a Unicode property.
+    pub(in crate::parser) fn add_code_points(
+        &mut self,
+        cps: &CodePointSet,
+    ) -> Result<(), CompileError> {
+        match self {
+            Self::Ascii(cc) => cc.add_code_points(cps),
+            Self::Utf8(cc) => cc.add_code_points(cps),
+        }
+    }
+
+    /// Adds a dash, which either forms a range or stands for itself.
+    pub(in crate::parser) fn add_dash(&mut self) -> Result<(), CompileError> {
+        match self {
+            Self::Ascii(cc) => cc.add_dash(),
+            Self::Utf8(cc) => cc.add_dash(),
+        }
+    }
+
+    pub(in crate::parser) fn negate(&mut self) {
+        match self {
+            Self::Ascii(cc) => cc.negate(),
+            Self::Utf8(cc) => cc.negate(),
+        }
+    }
+
+    /// Completes the class once all its members have been added.
+    pub(in crate::parser) fn finalize(&mut self) {
+        match self {
+            Self::Ascii(cc) => cc.finalize(),
+            Self::Utf8(cc) => cc.finalize(),
+        }
+    }
+
+    pub(in crate::parser) fn first(&self) -> Vec<PositionInfo> {
+        match self {
+            Self::Ascii(c) => c.first(),
+            Self::Utf8(c) => c.first(),
+        }
+    }
+
+    pub(in crate::parser) fn last(&self) -> Vec<PositionInfo> {
+        match self {
+            Self::Ascii(c) => c.last(),
+            Self::Utf8(c) => c.last(),
         }
-        Ok(())
     }
 
     /// Informs the Glushkov build process of the positions used by this component.
     pub(in crate::parser) fn note_positions(&mut self, bs: &mut GlushkovBuildState) {
         match self {
             Self::Ascii(c) => c.note_positions(bs),
+            Self::Utf8(c) => c.note_positions(bs),
+        }
+    }
+
+    pub(in crate::parser) fn build_follow_set(
+        &self,
+        bs: &mut GlushkovBuildState,
+        last_pos: &[PositionInfo],
+    ) {
+        match self {
+            Self::Ascii(c) => c.build_follow_set(bs, last_pos),
+            Self::Utf8(c) => c.build_follow_set(bs, last_pos),
         }
     }
 }
@@ -57,5 +216,6 @@ pub(crate) fn walk_component_class<V: ConstComponentVisitor>(
 ) -> Result<(), V::Error> {
     match c {
         ComponentClass::Ascii(c) => walk_ascii_component_class(v, c),
+        ComponentClass::Utf8(c) => walk_utf8_component_class(v, c),
     }
 }
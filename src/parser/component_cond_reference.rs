@@ -0,0 +1,55 @@
+use super::component_assertion::walk_component_assertion;
+use super::component_sequence::walk_component_sequence;
+use super::{ComponentAssertion, ComponentSequence, ConstComponentVisitor};
+
+/// Condition of a conditional subpattern.
+#[allow(dead_code)]
+#[derive(Clone, Debug)]
+pub(crate) enum Condition {
+    /// Whether the group with this index has matched, as in `(?(1)...)`.
+    Index(u32),
+    /// Whether the group with this name has matched, as in `(?(<n>)...)`.
+    Name(String),
+    /// Whether we are in a recursion, as in `(?(R)...)`.
+    Recursion,
+    /// Never true, as in `(?(DEFINE)...)`.
+    Define,
+    /// Whether an assertion holds, as in `(?(?=a)...)`.
+    Assertion(Box<ComponentAssertion>),
+}
+
+/// A conditional subpattern, `(?(condition)yes|no)`, whose sequence holds one
+/// or two branches.
+///
+/// This is parsed so that it can be reported, or matched as the alternation
+/// of its branches in prefilter mode, but cannot be compiled.
+#[derive(Clone, Debug)]
+pub(crate) struct ComponentCondReference {
+    pub(in crate::parser) condition: Condition,
+    pub(in crate::parser) seq: ComponentSequence,
+    /// Offset of the conditional in the pattern.
+    pub(in crate::parser) loc: usize,
+}
+
+impl ComponentCondReference {
+    pub(in crate::parser) fn new(condition: Condition, seq: ComponentSequence, loc: usize) -> Self {
+        Self {
+            condition,
+            seq,
+            loc,
+        }
+    }
+}
+
+pub(in crate::parser) fn walk_component_cond_reference<V: ConstComponentVisitor>(
+    v: &mut V,
+    c: &ComponentCondReference,
+) -> Result<(), V::Error> {
+    v.pre_component_cond_reference(c)?;
+    if let Condition::Assertion(a) = &c.condition {
+        walk_component_assertion(v, a)?;
+    }
+    walk_component_sequence(v, &c.seq)?;
+    v.post_component_cond_reference(c);
+    Ok(())
+}
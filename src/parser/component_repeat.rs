@@ -0,0 +1,187 @@
+use super::build_state::{epsilon, has_epsilon, is_epsilon, replace_epsilons};
+use super::{
+    walk_component, Component, ConstComponentVisitor, GlushkovBuildState, PosFlags, PositionInfo,
+};
+use crate::util::compile_error::CompileError;
+use std::iter;
+
+/// Flavour of a quantifier.
+///
+/// Since every match is reported, non-greedy repeats behave exactly like
+/// greedy ones.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum RepeatType {
+    Greedy,
+    NonGreedy,
+    Possessive,
+}
+
+/// A component repeated between `min` and `max` times.
+#[derive(Clone, Debug)]
+pub(crate) struct ComponentRepeat {
+    sub_comp: Box<Component>,
+    pub(in crate::parser) min: u32,
+    /// Maximum number of repeats, or `None` if unbounded.
+    pub(in crate::parser) max: Option<u32>,
+    pub(in crate::parser) kind: RepeatType,
+
+    /// Further copies of `sub_comp`, made when positions are noted so that
+    /// each repeat has positions of its own.
+    copies: Vec<Component>,
+}
+
+impl ComponentRepeat {
+    pub(in crate::parser) fn new(
+        sub_comp: Component,
+        min: u32,
+        max: Option<u32>,
+        kind: RepeatType,
+    ) -> Self {
+        debug_assert!(max.map_or(true, |max| min <= max));
+        Self {
+            sub_comp: Box::new(sub_comp),
+            min,
+            max,
+            kind,
+            copies: Vec::new(),
+        }
+    }
+
+    pub(in crate::parser) fn sub_component_mut(&mut self) -> &mut Component {
+        &mut self.sub_comp
+    }
+
+    /// Returns the number of copies of the repeated region in the graph:
+    /// one per possible repeat if bounded, and enough to cover the minimum
+    /// otherwise, the last one looping onto itself.
+    fn num_copies(&self) -> usize {
+        match self.max {
+            Some(max) => max as usize,
+            None => self.min.max(1) as usize,
+        }
+    }
+
+    /// Iterates over all copies of the repeated region, in order.
+    fn all_copies(&self) -> impl Iterator<Item = &Component> {
+        iter::once(&*self.sub_comp)
+            .chain(self.copies.iter())
+            .take(self.num_copies())
+    }
+
+    pub(in crate::parser) fn first(&self) -> Vec<PositionInfo> {
+        let mut firsts = vec![epsilon(PosFlags::empty())];
+        for c in self.all_copies() {
+            if !has_epsilon(&firsts) {
+                break;
+            }
+            replace_epsilons(&mut firsts, &c.first());
+        }
+        // The region may be skipped altogether, whatever assertions the
+        // epsilons through it carry.
+        let skip = epsilon(PosFlags::empty());
+        if self.min == 0 && !firsts.contains(&skip) {
+            firsts.push(skip);
+        }
+        firsts
+    }
+
+    pub(in crate::parser) fn last(&self) -> Vec<PositionInfo> {
+        let mut lasts = Vec::new();
+        let mut prev = vec![epsilon(PosFlags::empty())];
+        for (i, c) in self.all_copies().enumerate() {
+            let mut copy_lasts = c.last();
+            replace_epsilons(&mut copy_lasts, &prev);
+            // We may stop after any copy once the minimum is satisfied.
+            if i + 1 >= self.min as usize {
+                for p in &copy_lasts {
+                    if !lasts.contains(p) {
+                        lasts.push(*p);
+                    }
+                }
+            }
+            prev = copy_lasts;
+        }
+        // The region may be skipped altogether, whatever assertions the
+        // epsilons through it carry.
+        let skip = epsilon(PosFlags::empty());
+        if self.min == 0 && !lasts.contains(&skip) {
+            lasts.push(skip);
+        }
+        lasts
+    }
+
+    /// Informs the Glushkov build process of the positions used by this component.
+    pub(in crate::parser) fn note_positions(&mut self, bs: &mut GlushkovBuildState) {
+        // Nested repeats multiply their copies; don't keep copying once the
+        // graph is too large to be accepted.
+        if bs.get_builder().is_too_large() {
+            return;
+        }
+        let extra = self.num_copies().saturating_sub(1);
+        self.copies = vec![(*self.sub_comp).clone(); extra];
+        if self.num_copies() == 0 {
+            return;
+        }
+        self.sub_comp.note_positions(bs);
+        for c in &mut self.copies {
+            c.note_positions(bs);
+        }
+    }
+
+    pub(in crate::parser) fn build_follow_set(
+        &self,
+        bs: &mut GlushkovBuildState,
+        last_pos: &[PositionInfo],
+    ) {
+        // Chain the copies one after the other, as in a sequence.
+        let mut lasts = last_pos.to_vec();
+        for c in self.all_copies() {
+            c.build_follow_set(bs, &lasts);
+            let mut next = c.last();
+            replace_epsilons(&mut next, &lasts);
+            lasts = next;
+        }
+
+        // Without an upper bound, the last copy loops onto itself.
+        if self.max.is_none() {
+            if let Some(c) = self.all_copies().last() {
+                let mut loop_lasts = c.last();
+                loop_lasts.retain(|p| !is_epsilon(p));
+                bs.connect_regions(&loop_lasts, &c.first());
+            }
+        }
+    }
+
+    pub(in crate::parser) fn check_embedded_start_anchor(
+        &self,
+        mut at_start: bool,
+    ) -> Result<bool, CompileError> {
+        at_start = self.sub_comp.check_embedded_start_anchor(at_start)?;
+        if self.max != Some(1) {
+            // A second iteration follows the first one.
+            at_start = self.sub_comp.check_embedded_start_anchor(at_start)?;
+        }
+        Ok(at_start)
+    }
+
+    pub(in crate::parser) fn check_embedded_end_anchor(
+        &self,
+        mut at_end: bool,
+    ) -> Result<bool, CompileError> {
+        at_end = self.sub_comp.check_embedded_end_anchor(at_end)?;
+        if self.max != Some(1) {
+            at_end = self.sub_comp.check_embedded_end_anchor(at_end)?;
+        }
+        Ok(at_end)
+    }
+}
+
+pub(in crate::parser) fn walk_component_repeat<V: ConstComponentVisitor>(
+    v: &mut V,
+    c: &ComponentRepeat,
+) -> Result<(), V::Error> {
+    v.pre_component_repeat(c)?;
+    walk_component(v, &c.sub_comp)?;
+    v.post_component_repeat(c);
+    Ok(())
+}
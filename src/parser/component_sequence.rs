@@ -1,15 +1,17 @@
+use super::build_state::{epsilon, has_epsilon, replace_epsilons};
 use super::{
-    walk_component, Component, ComponentAlternation, ConstComponentVisitor, GlushkovBuildState,
+    walk_component, Component, ComponentAlternation, ComponentRepeat, ConstComponentVisitor,
+    GlushkovBuildState, PosFlags, PositionInfo, RepeatType,
 };
+use crate::util::compile_error::CompileError;
 use std::mem;
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub(crate) struct ComponentSequence {
     children: Vec<Component>,
     alternation: Option<ComponentAlternation>,
 
     pub(super) capture_index: Option<u32>,
-    #[allow(dead_code)]
     pub(super) capture_name: Option<String>,
 }
 
@@ -25,6 +27,14 @@ impl ComponentSequence {
         alternation.append(seq);
     }
 
+    /// Returns the number of branches of the sequence, before it is
+    /// finalized.
+    pub(in crate::parser) fn num_branches(&self) -> usize {
+        self.alternation
+            .as_ref()
+            .map_or(1, |a| a.num_branches() + 1)
+    }
+
     pub(in crate::parser) fn finalize(&mut self) {
         if self.alternation.is_some() {
             self.add_alternation();
@@ -38,6 +48,51 @@ impl ComponentSequence {
         self.children.push(comp);
     }
 
+    /// Applies a quantifier to the last component of the sequence.
+    ///
+    /// Returns `false` if there is no component the quantifier may apply to.
+    pub(in crate::parser) fn add_repeat(
+        &mut self,
+        min: u32,
+        max: Option<u32>,
+        kind: RepeatType,
+    ) -> bool {
+        match self.children.pop() {
+            Some(c) if c.repeatable() => {
+                self.children
+                    .push(ComponentRepeat::new(c, min, max, kind).into());
+                true
+            }
+            Some(c) => {
+                self.children.push(c);
+                false
+            }
+            None => false,
+        }
+    }
+
+    pub(in crate::parser) fn first(&self) -> Vec<PositionInfo> {
+        let mut firsts = vec![epsilon(PosFlags::empty())];
+        for c in &self.children {
+            if !has_epsilon(&firsts) {
+                break;
+            }
+            replace_epsilons(&mut firsts, &c.first());
+        }
+        firsts
+    }
+
+    pub(in crate::parser) fn last(&self) -> Vec<PositionInfo> {
+        let mut lasts = vec![epsilon(PosFlags::empty())];
+        for c in self.children.iter().rev() {
+            if !has_epsilon(&lasts) {
+                break;
+            }
+            replace_epsilons(&mut lasts, &c.last());
+        }
+        lasts
+    }
+
     /// Informs the Glushkov build process of the positions used by this component.
     pub(in crate::parser) fn note_positions(&mut self, bs: &mut GlushkovBuildState) {
         for c in &mut self.children {
@@ -45,10 +100,53 @@ impl ComponentSequence {
         }
     }
 
+    pub(in crate::parser) fn build_follow_set(
+        &self,
+        bs: &mut GlushkovBuildState,
+        last_pos: &[PositionInfo],
+    ) {
+        let mut lasts = last_pos.to_vec();
+        for c in &self.children {
+            c.build_follow_set(bs, &lasts);
+            let mut next = c.last();
+            replace_epsilons(&mut next, &lasts);
+            lasts = next;
+        }
+    }
+
+    pub(in crate::parser) fn check_embedded_start_anchor(
+        &self,
+        mut at_start: bool,
+    ) -> Result<bool, CompileError> {
+        for c in &self.children {
+            at_start = c.check_embedded_start_anchor(at_start)?;
+        }
+        Ok(at_start)
+    }
+
+    pub(in crate::parser) fn check_embedded_end_anchor(
+        &self,
+        mut at_end: bool,
+    ) -> Result<bool, CompileError> {
+        for c in self.children.iter().rev() {
+            at_end = c.check_embedded_end_anchor(at_end)?;
+        }
+        Ok(at_end)
+    }
+
     #[cfg(test)]
     pub(super) fn children(&self) -> &Vec<Component> {
         &self.children
     }
+
+    pub(super) fn children_mut(&mut self) -> &mut Vec<Component> {
+        &mut self.children
+    }
+
+    /// Returns whether a finalized sequence is an alternation.
+    pub(super) fn is_alternation(&self) -> bool {
+        matches!(self.children[..], [Component::Alternation(_)])
+    }
 }
 
 pub(in crate::parser) fn walk_component_sequence<V: ConstComponentVisitor>(
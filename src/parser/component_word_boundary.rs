@@ -0,0 +1,47 @@
+use super::build_state::epsilon;
+use super::{ConstComponentVisitor, PosFlags, PositionInfo};
+
+/// A word boundary (`\b`) or non-boundary (`\B`) assertion.
+#[derive(Clone, Debug)]
+pub(crate) struct ComponentWordBoundary {
+    pub(in crate::parser) negated: bool,
+    pub(in crate::parser) ucp: bool,
+}
+
+impl ComponentWordBoundary {
+    pub(in crate::parser) fn new(negated: bool, ucp: bool) -> Self {
+        Self { negated, ucp }
+    }
+
+    /// Returns the assertion flags carried over to the edges crossing the
+    /// boundary.
+    fn flags(&self) -> PosFlags {
+        match (self.negated, self.ucp) {
+            (false, false) => PosFlags::ASSERT_WORD_TO_NONWORD | PosFlags::ASSERT_NONWORD_TO_WORD,
+            (true, false) => PosFlags::ASSERT_WORD_TO_WORD | PosFlags::ASSERT_NONWORD_TO_NONWORD,
+            (false, true) => {
+                PosFlags::ASSERT_WORD_TO_NONWORD_UCP | PosFlags::ASSERT_NONWORD_TO_WORD_UCP
+            }
+            (true, true) => {
+                PosFlags::ASSERT_WORD_TO_WORD_UCP | PosFlags::ASSERT_NONWORD_TO_NONWORD_UCP
+            }
+        }
+    }
+
+    pub(in crate::parser) fn first(&self) -> Vec<PositionInfo> {
+        vec![epsilon(self.flags())]
+    }
+
+    pub(in crate::parser) fn last(&self) -> Vec<PositionInfo> {
+        self.first()
+    }
+}
+
+pub(in crate::parser) fn walk_component_word_boundary<V: ConstComponentVisitor>(
+    v: &mut V,
+    c: &ComponentWordBoundary,
+) -> Result<(), V::Error> {
+    v.pre_component_word_boundary(c)?;
+    v.post_component_word_boundary(c);
+    Ok(())
+}
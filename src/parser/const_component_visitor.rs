@@ -1,18 +1,47 @@
 use super::ascii_component_class::AsciiComponentClass;
-use super::{ComponentAlternation, ComponentSequence};
+use super::utf8_component_class::Utf8ComponentClass;
+use super::{
+    ComponentAlternation, ComponentAssertion, ComponentAtomicGroup, ComponentBackReference,
+    ComponentBoundary, ComponentCondReference, ComponentRepeat, ComponentSequence,
+    ComponentWordBoundary,
+};
 
 pub(crate) trait ConstComponentVisitor {
     type Error;
 
     fn pre_ascii_component_class(&mut self, c: &AsciiComponentClass) -> Result<(), Self::Error>;
     fn pre_component_alternation(&mut self, c: &ComponentAlternation) -> Result<(), Self::Error>;
+    fn pre_component_assertion(&mut self, c: &ComponentAssertion) -> Result<(), Self::Error>;
+    fn pre_component_atomic_group(&mut self, c: &ComponentAtomicGroup) -> Result<(), Self::Error>;
+    fn pre_component_back_reference(
+        &mut self,
+        c: &ComponentBackReference,
+    ) -> Result<(), Self::Error>;
+    fn pre_component_boundary(&mut self, c: &ComponentBoundary) -> Result<(), Self::Error>;
+    fn pre_component_cond_reference(
+        &mut self,
+        c: &ComponentCondReference,
+    ) -> Result<(), Self::Error>;
+    fn pre_component_repeat(&mut self, c: &ComponentRepeat) -> Result<(), Self::Error>;
     fn pre_component_sequence(&mut self, c: &ComponentSequence) -> Result<(), Self::Error>;
+    fn pre_utf8_component_class(&mut self, c: &Utf8ComponentClass) -> Result<(), Self::Error>;
+    fn pre_component_word_boundary(&mut self, c: &ComponentWordBoundary)
+        -> Result<(), Self::Error>;
 
     fn during_ascii_component_class(&self, c: &AsciiComponentClass);
     fn during_component_alternation(&self, c: &ComponentAlternation);
     fn during_component_sequence(&self, c: &ComponentSequence);
+    fn during_utf8_component_class(&self, c: &Utf8ComponentClass);
 
     fn post_ascii_component_class(&mut self, c: &AsciiComponentClass);
     fn post_component_alternation(&mut self, c: &ComponentAlternation);
+    fn post_component_assertion(&mut self, c: &ComponentAssertion);
+    fn post_component_atomic_group(&mut self, c: &ComponentAtomicGroup);
+    fn post_component_back_reference(&mut self, c: &ComponentBackReference);
+    fn post_component_boundary(&mut self, c: &ComponentBoundary);
+    fn post_component_cond_reference(&mut self, c: &ComponentCondReference);
+    fn post_component_repeat(&mut self, c: &ComponentRepeat);
     fn post_component_sequence(&mut self, c: &ComponentSequence);
+    fn post_utf8_component_class(&mut self, c: &Utf8ComponentClass);
+    fn post_component_word_boundary(&mut self, c: &ComponentWordBoundary);
 }
@@ -61,11 +61,11 @@ mod tests {
     fn read_control_verbs_supported() {
         let mut mode = ParseMode::default();
         assert!(!mode.ucp);
-        let rest = read_control_verbs(&"(*UCP)test"[..], 0, &mut mode);
+        let rest = read_control_verbs("(*UCP)test", 0, &mut mode);
         assert!(mode.ucp);
         assert_eq!(rest.expect("invalid test"), "test");
         assert!(!mode.utf8);
-        let rest = read_control_verbs(&"(*UTF)test"[..], 0, &mut mode);
+        let rest = read_control_verbs("(*UTF)test", 0, &mut mode);
         assert!(mode.utf8);
         assert_eq!(rest.expect("invalid test"), "test");
     }
@@ -73,12 +73,12 @@ mod tests {
     #[test]
     fn read_control_verbs_unsupported() {
         let mut mode = ParseMode::default();
-        let rest = read_control_verbs(&"(*ANY)test"[..], 0, &mut mode);
+        let rest = read_control_verbs("(*ANY)test", 0, &mut mode);
         assert!(rest
             .expect_err("invalid test")
             .reason
             .starts_with("Unsupported"));
-        let rest = read_control_verbs(&"(*LIMIT_MATCH=3)test"[..], 0, &mut mode);
+        let rest = read_control_verbs("(*LIMIT_MATCH=3)test", 0, &mut mode);
         assert!(rest
             .expect_err("invalid test")
             .reason
@@ -88,7 +88,7 @@ mod tests {
     #[test]
     fn read_control_verbs_unknown() {
         let mut mode = ParseMode::default();
-        let rest = read_control_verbs(&"(*XXX)test"[..], 0, &mut mode);
+        let rest = read_control_verbs("(*XXX)test", 0, &mut mode);
         assert!(rest
             .expect_err("invalid test")
             .reason
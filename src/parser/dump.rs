@@ -1,4 +1,5 @@
 use super::ascii_component_class::AsciiComponentClass;
+use super::utf8_component_class::Utf8ComponentClass;
 use crate::parser::*;
 use crate::util::{describe_class, CcOutput};
 use std::fmt;
@@ -54,6 +55,105 @@ impl<'w, W: Write> ConstComponentVisitor for DumpVisitor<'w, W> {
         self.outdent();
     }
 
+    fn pre_component_assertion(&mut self, c: &ComponentAssertion) -> Result<(), Self::Error> {
+        self.out.write_str(&self.filler())?;
+        if c.negated {
+            self.out.write_str("NEGATIVE ")?;
+        }
+        self.out.write_str(match c.dir {
+            AssertionDir::Lookahead => "LOOKAHEAD\n",
+            AssertionDir::Lookbehind => "LOOKBEHIND\n",
+        })?;
+        self.indent();
+        Ok(())
+    }
+
+    fn post_component_assertion(&mut self, _c: &ComponentAssertion) {
+        self.outdent();
+    }
+
+    fn pre_component_atomic_group(&mut self, _c: &ComponentAtomicGroup) -> Result<(), Self::Error> {
+        self.out.write_str(&(self.filler() + "ATOMIC GROUP\n"))?;
+        self.indent();
+        Ok(())
+    }
+
+    fn post_component_atomic_group(&mut self, _c: &ComponentAtomicGroup) {
+        self.outdent();
+    }
+
+    fn pre_component_back_reference(
+        &mut self,
+        c: &ComponentBackReference,
+    ) -> Result<(), Self::Error> {
+        match &c.reference {
+            BackRef::Index(index) => {
+                self.out
+                    .write_str(&format!("{}BACKREF {}\n", self.filler(), index))
+            }
+            BackRef::Name(name) => {
+                self.out
+                    .write_str(&format!("{}BACKREF '{}'\n", self.filler(), name))
+            }
+        }
+    }
+
+    fn post_component_back_reference(&mut self, _c: &ComponentBackReference) {}
+
+    fn pre_component_cond_reference(
+        &mut self,
+        c: &ComponentCondReference,
+    ) -> Result<(), Self::Error> {
+        self.out
+            .write_str(&format!("{}CONDITIONAL ", self.filler()))?;
+        match &c.condition {
+            Condition::Index(index) => self.out.write_str(&format!("(group {})\n", index))?,
+            Condition::Name(name) => self.out.write_str(&format!("(group '{}')\n", name))?,
+            Condition::Recursion => self.out.write_str("(recursion)\n")?,
+            Condition::Define => self.out.write_str("(define)\n")?,
+            Condition::Assertion(_) => self.out.write_str("(assertion)\n")?,
+        }
+        self.indent();
+        Ok(())
+    }
+
+    fn post_component_cond_reference(&mut self, _c: &ComponentCondReference) {
+        self.outdent();
+    }
+
+    fn pre_component_boundary(&mut self, c: &ComponentBoundary) -> Result<(), Self::Error> {
+        self.out.write_str(&self.filler())?;
+        self.out.write_str(match c.bound {
+            Boundary::BeginString => "BEGIN_STRING\n",
+            Boundary::EndString => "END_STRING\n",
+            Boundary::EndStringOptionalLf => "END_STRING_OPTIONAL_LF\n",
+            Boundary::BeginLine => "BEGIN_LINE\n",
+            Boundary::EndLine => "END_LINE\n",
+        })
+    }
+
+    fn post_component_boundary(&mut self, _c: &ComponentBoundary) {}
+
+    fn pre_component_repeat(&mut self, c: &ComponentRepeat) -> Result<(), Self::Error> {
+        self.out
+            .write_str(&format!("{}REPEAT ({}, ", self.filler(), c.min))?;
+        match c.max {
+            Some(max) => self.out.write_str(&format!("{}) ", max))?,
+            None => self.out.write_str("inf) ")?,
+        }
+        self.out.write_str(match c.kind {
+            RepeatType::Greedy => "greedy\n",
+            RepeatType::NonGreedy => "non-greedy\n",
+            RepeatType::Possessive => "possessive\n",
+        })?;
+        self.indent();
+        Ok(())
+    }
+
+    fn post_component_repeat(&mut self, _c: &ComponentRepeat) {
+        self.outdent();
+    }
+
     fn pre_component_sequence(&mut self, c: &ComponentSequence) -> Result<(), Self::Error> {
         self.out
             .write_str(&format!("{}{}", self.filler(), "SEQUENCE"))?;
@@ -80,10 +180,41 @@ impl<'w, W: Write> ConstComponentVisitor for DumpVisitor<'w, W> {
         self.outdent();
     }
 
+    fn pre_utf8_component_class(&mut self, c: &Utf8ComponentClass) -> Result<(), Self::Error> {
+        self.out.write_str(&format!(
+            "{}UTF8 CLASS\n{}  {}\n",
+            self.filler(),
+            self.filler(),
+            c.cps
+        ))?;
+        self.indent();
+        Ok(())
+    }
+
+    fn post_utf8_component_class(&mut self, _c: &Utf8ComponentClass) {
+        self.outdent();
+    }
+
+    fn pre_component_word_boundary(
+        &mut self,
+        c: &ComponentWordBoundary,
+    ) -> Result<(), Self::Error> {
+        self.out.write_str(&self.filler())?;
+        self.out.write_str(if c.negated {
+            "NON-WORD-BOUNDARY"
+        } else {
+            "WORD-BOUNDARY"
+        })?;
+        self.out.write_str(if c.ucp { " (UCP)\n" } else { "\n" })
+    }
+
+    fn post_component_word_boundary(&mut self, _c: &ComponentWordBoundary) {}
+
     // not used
     fn during_ascii_component_class(&self, _c: &AsciiComponentClass) {}
     fn during_component_alternation(&self, _c: &ComponentAlternation) {}
     fn during_component_sequence(&self, _c: &ComponentSequence) {}
+    fn during_utf8_component_class(&self, _c: &Utf8ComponentClass) {}
 }
 
 pub(crate) fn dump_tree<W: Write>(out: &mut W, root: &Component) -> Result<(), fmt::Error> {
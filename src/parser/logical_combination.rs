@@ -0,0 +1,241 @@
+use crate::util::compile_error::{CompileError, ErrorKind};
+use crate::util::{CombInfo, LogicalOp, ReportId};
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+
+/// Logical combinations of a database, and the logical keys given to the
+/// sub-expressions they refer to.
+#[derive(Default)]
+pub(crate) struct ParsedLogical {
+    /// Mapping from sub-expression ID to logical key.
+    to_logical_key_map: BTreeMap<ReportId, u32>,
+
+    /// Combinations, with the index of their expression.
+    combinations: Vec<(usize, CombInfo)>,
+}
+
+impl ParsedLogical {
+    /// Fetches the logical key of the sub-expression with the given ID,
+    /// assigning one if necessary.
+    fn get_logical_key(&mut self, id: ReportId) -> u32 {
+        let size = self
+            .to_logical_key_map
+            .len()
+            .try_into()
+            .expect("too many sub-expressions");
+        *self.to_logical_key_map.entry(id).or_insert(size)
+    }
+
+    /// Returns the logical key of the sub-expression with the given ID, if it
+    /// is part of a combination.
+    pub(crate) fn logical_key(&self, id: ReportId) -> Option<u32> {
+        self.to_logical_key_map.get(&id).copied()
+    }
+
+    /// Number of logical keys given to sub-expressions.
+    pub(crate) fn num_logical_keys(&self) -> u32 {
+        self.to_logical_key_map
+            .len()
+            .try_into()
+            .expect("too many sub-expressions")
+    }
+
+    /// Returns the combinations, in the order they were parsed.
+    pub(crate) fn combinations(&self) -> impl Iterator<Item = &CombInfo> {
+        self.combinations.iter().map(|(_, comb)| comb)
+    }
+
+    /// Parses the combination of expression `index`, reported with ID `id`.
+    pub(crate) fn parse_logical_combination(
+        &mut self,
+        index: usize,
+        id: ReportId,
+        logical: &str,
+        quiet: bool,
+    ) -> Result<(), CompileError> {
+        let ops = parse_combination(logical)?
+            .into_iter()
+            .map(|op| match op {
+                LogicalOp::Key(sub_id) => LogicalOp::Key(self.get_logical_key(sub_id)),
+                op => op,
+            })
+            .collect();
+        self.combinations.push((index, CombInfo { id, quiet, ops }));
+        Ok(())
+    }
+
+    /// Checks that the combinations only refer to the IDs of expressions
+    /// which are not combinations themselves.
+    pub(crate) fn validate_sub_ids(
+        &self,
+        ids: &[ReportId],
+        combination: &[bool],
+    ) -> Result<(), CompileError> {
+        for (&sub_id, &lkey) in &self.to_logical_key_map {
+            let mut found = false;
+            for (&id, &comb) in ids.iter().zip(combination) {
+                if id == sub_id {
+                    if comb {
+                        return Err(
+                            self.comb_error(lkey, "No logical combination of logical combination.")
+                        );
+                    }
+                    found = true;
+                }
+            }
+            if !found {
+                return Err(
+                    self.comb_error(lkey, &format!("Unknown sub-expression id {}.", sub_id))
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Builds an error for the first combination referring to `lkey`.
+    fn comb_error(&self, lkey: u32, why: &str) -> CompileError {
+        let (index, _) = self
+            .combinations
+            .iter()
+            .find(|(_, comb)| comb.ops.contains(&LogicalOp::Key(lkey)))
+            .expect("logical keys are given to referenced ids");
+        CompileError::with_index((*index).try_into().expect("too many expressions"), why)
+    }
+}
+
+/// Parses a logical combination such as `(101 & 102) | !103`, returning it in
+/// postfix order with the IDs of the sub-expressions as keys.
+///
+/// `!` binds tighter than `&`, which binds tighter than `|`.
+pub(crate) fn parse_combination(logical: &str) -> Result<Vec<LogicalOp>, CompileError> {
+    let mut p = CombinationParser {
+        input: logical.as_bytes(),
+        pos: 0,
+        ops: Vec::new(),
+    };
+    p.or()?;
+    match p.peek() {
+        None => Ok(p.ops),
+        Some(b')') => Err(p.located_error("Not enough left parentheses")),
+        Some(_) => Err(p.located_error("Not enough operator")),
+    }
+}
+
+struct CombinationParser<'a> {
+    input: &'a [u8],
+    pos: usize,
+    ops: Vec<LogicalOp>,
+}
+
+impl CombinationParser<'_> {
+    /// Builds a parse error located at the current position.
+    fn located_error(&self, why: &str) -> CompileError {
+        CompileError::new(
+            ErrorKind::LocatedParse,
+            format!("{} at index {}", why, self.pos),
+        )
+    }
+
+    /// Skips whitespace and returns the next character, if any.
+    fn peek(&mut self) -> Option<u8> {
+        while self.pos < self.input.len() && self.input[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+        self.input.get(self.pos).copied()
+    }
+
+    fn or(&mut self) -> Result<(), CompileError> {
+        self.and()?;
+        while self.peek() == Some(b'|') {
+            self.pos += 1;
+            self.and()?;
+            self.ops.push(LogicalOp::Or);
+        }
+        Ok(())
+    }
+
+    fn and(&mut self) -> Result<(), CompileError> {
+        self.not()?;
+        while self.peek() == Some(b'&') {
+            self.pos += 1;
+            self.not()?;
+            self.ops.push(LogicalOp::And);
+        }
+        Ok(())
+    }
+
+    fn not(&mut self) -> Result<(), CompileError> {
+        match self.peek() {
+            Some(b'!') => {
+                self.pos += 1;
+                self.not()?;
+                self.ops.push(LogicalOp::Not);
+            }
+            Some(b'(') => {
+                self.pos += 1;
+                self.or()?;
+                match self.peek() {
+                    Some(b')') => self.pos += 1,
+                    None => return Err(self.located_error("Not enough right parentheses")),
+                    Some(_) => return Err(self.located_error("Not enough operator")),
+                }
+            }
+            Some(c) if c.is_ascii_digit() => {
+                let start = self.pos;
+                while self.pos < self.input.len() && self.input[self.pos].is_ascii_digit() {
+                    self.pos += 1;
+                }
+                let digits = std::str::from_utf8(&self.input[start..self.pos]).expect("ASCII");
+                if let Ok(id) = digits.parse::<ReportId>() {
+                    self.ops.push(LogicalOp::Key(id));
+                } else {
+                    self.pos = start;
+                    return Err(self.located_error("Sub-expression id too large"));
+                }
+            }
+            Some(b')') | Some(b'&') | Some(b'|') | None => {
+                return Err(self.located_error("Not enough operand"))
+            }
+            Some(_) => return Err(self.located_error("Unknown character")),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use LogicalOp::{And, Key, Not, Or};
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            parse_combination("(101 & 102) | !103").expect("valid"),
+            vec![Key(101), Key(102), And, Key(103), Not, Or]
+        );
+        assert_eq!(
+            parse_combination("1|2&!!3").expect("valid"),
+            vec![Key(1), Key(2), Key(3), Not, Not, And, Or]
+        );
+        assert_eq!(parse_combination(" ( 7 ) ").expect("valid"), vec![Key(7)]);
+    }
+
+    #[test]
+    fn parse_errors() {
+        for (logical, reason) in &[
+            ("", "Not enough operand at index 0"),
+            ("1 &", "Not enough operand at index 3"),
+            ("1 & | 2", "Not enough operand at index 4"),
+            ("(1 | 2", "Not enough right parentheses at index 6"),
+            ("1 | 2)", "Not enough left parentheses at index 5"),
+            ("1 2", "Not enough operator at index 2"),
+            ("(1 2)", "Not enough operator at index 3"),
+            ("1 & a", "Unknown character at index 4"),
+            ("99999999999", "Sub-expression id too large at index 0"),
+        ] {
+            let e = parse_combination(logical).expect_err("invalid");
+            assert!(matches!(e.kind(), ErrorKind::LocatedParse));
+            assert_eq!(&e.reason, reason);
+        }
+    }
+}
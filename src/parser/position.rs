@@ -8,7 +8,102 @@ bitflags! {
         const NOFLOAT = 0x0000_0001; // don't wire to start-dotstar
         const MUST_FLOAT = 0x0000_0010; // don't wire solely to start
         const MULTILINE_START = 0x0000_0100;
+        const WIRE_EOD = 0x0000_1000; // wire to accept_eod
+        const WIRE_NL_EOD = 0x0000_2000; // wire to accept_eod through a newline
+        const WIRE_NL_ACCEPT = 0x0000_4000; // wire to accept through a newline
+        const NO_NL_EOD = 0x0000_8000; // don't wire to accept_eod through a newline
+        const NO_NL_ACCEPT = 0x0001_0000; // don't wire to accept through a newline
+        const ONLY_ENDS = 0x0002_0000; // don't wire to accept
+        const ASSERT_WORD_TO_NONWORD = 0x0004_0000;
+        const ASSERT_NONWORD_TO_WORD = 0x0008_0000;
+        const ASSERT_WORD_TO_WORD = 0x0010_0000;
+        const ASSERT_NONWORD_TO_NONWORD = 0x0020_0000;
+        const ASSERT_WORD_TO_NONWORD_UCP = 0x0040_0000;
+        const ASSERT_NONWORD_TO_WORD_UCP = 0x0080_0000;
+        const ASSERT_WORD_TO_WORD_UCP = 0x0100_0000;
+        const ASSERT_NONWORD_TO_NONWORD_UCP = 0x0200_0000;
+        const WORD_CHAR = 0x0400_0000; // part of a UCP word character
+        const NONWORD_CHAR = 0x0800_0000; // part of a UCP non-word character
+
+        const WORD_ASSERTS = Self::ASSERT_WORD_TO_NONWORD.bits
+            | Self::ASSERT_NONWORD_TO_WORD.bits
+            | Self::ASSERT_WORD_TO_WORD.bits
+            | Self::ASSERT_NONWORD_TO_NONWORD.bits;
+        const UCP_WORD_ASSERTS = Self::ASSERT_WORD_TO_NONWORD_UCP.bits
+            | Self::ASSERT_NONWORD_TO_WORD_UCP.bits
+            | Self::ASSERT_WORD_TO_WORD_UCP.bits
+            | Self::ASSERT_NONWORD_TO_NONWORD_UCP.bits;
+        const ALL_WORD_ASSERTS = Self::WORD_ASSERTS.bits | Self::UCP_WORD_ASSERTS.bits;
+    }
+}
+
+impl PosFlags {
+    /// Combines the flags of two positions matched one after the other.
+    ///
+    /// Anchoring flags add up, while word boundary assertions must all hold:
+    /// only the transitions allowed by both are kept. Returns `None` if no
+    /// transition is left.
+    pub(crate) fn chain(self, other: Self) -> Option<Self> {
+        const TRANSITIONS: [(PosFlags, PosFlags); 4] = [
+            (
+                PosFlags::ASSERT_WORD_TO_NONWORD,
+                PosFlags::ASSERT_WORD_TO_NONWORD_UCP,
+            ),
+            (
+                PosFlags::ASSERT_NONWORD_TO_WORD,
+                PosFlags::ASSERT_NONWORD_TO_WORD_UCP,
+            ),
+            (
+                PosFlags::ASSERT_WORD_TO_WORD,
+                PosFlags::ASSERT_WORD_TO_WORD_UCP,
+            ),
+            (
+                PosFlags::ASSERT_NONWORD_TO_NONWORD,
+                PosFlags::ASSERT_NONWORD_TO_NONWORD_UCP,
+            ),
+        ];
+
+        let others = (self | other) - Self::ALL_WORD_ASSERTS;
+        let (a, b) = (
+            self & Self::ALL_WORD_ASSERTS,
+            other & Self::ALL_WORD_ASSERTS,
+        );
+        if a.is_empty() || b.is_empty() {
+            return Some(others | a | b);
+        }
+
+        let ucp = (a | b).intersects(Self::UCP_WORD_ASSERTS);
+        let mut asserts = Self::empty();
+        for &(plain, with_ucp) in &TRANSITIONS {
+            if a.intersects(plain | with_ucp) && b.intersects(plain | with_ucp) {
+                asserts |= if ucp { with_ucp } else { plain };
+            }
+        }
+        if asserts.is_empty() {
+            None
+        } else {
+            Some(others | asserts)
+        }
     }
 }
 
 pub(crate) type Position = NfaVertex;
+
+#[cfg(test)]
+mod tests {
+    use super::PosFlags;
+
+    #[test]
+    fn chain() {
+        let b = PosFlags::ASSERT_WORD_TO_NONWORD | PosFlags::ASSERT_NONWORD_TO_WORD;
+        let nb = PosFlags::ASSERT_WORD_TO_WORD | PosFlags::ASSERT_NONWORD_TO_NONWORD;
+        assert_eq!(b.chain(b), Some(b));
+        assert_eq!(b.chain(nb), None);
+        assert_eq!(nb.chain(b), None);
+        assert_eq!(PosFlags::NOFLOAT.chain(b), Some(PosFlags::NOFLOAT | b));
+        assert_eq!(
+            (b | PosFlags::ASSERT_WORD_TO_WORD).chain(nb),
+            Some(PosFlags::ASSERT_WORD_TO_WORD)
+        );
+    }
+}
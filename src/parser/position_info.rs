@@ -1,11 +1,17 @@
 use crate::parser::{PosFlags, Position};
 
-#[derive(Clone, Copy, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub(crate) struct PositionInfo {
     pub(in crate::parser) pos: Position,
     pub(in crate::parser) flags: PosFlags,
 }
 
+impl PositionInfo {
+    pub(in crate::parser) fn with_flags(pos: Position, flags: PosFlags) -> Self {
+        Self { pos, flags }
+    }
+}
+
 impl From<Position> for PositionInfo {
     fn from(pos: Position) -> Self {
         Self {
@@ -1,3 +1,116 @@
-use crate::parser::Component;
+use super::{
+    get_component_class, Component, ComponentRepeat, ComponentSequence, ParseMode, PredefinedClass,
+    RepeatType,
+};
+use std::mem;
 
-pub(crate) fn prefilter_tree(_root: &Component) {}
+/// Largest repeat bound kept in prefilter mode; repeats with larger bounds
+/// are widened to unbounded ones.
+const MAX_PREFILTER_REPEAT_BOUND: u32 = 100;
+
+/// Rewrites the parse tree of a prefilter expression so that constructs which
+/// cannot be compiled are replaced by ones matching a superset of what they
+/// match.
+pub(crate) fn prefilter_tree(root: &mut Component, mode: ParseMode) {
+    prefilter_component(root, mode);
+}
+
+fn prefilter_component(c: &mut Component, mode: ParseMode) {
+    let replacement = match c {
+        Component::Sequence(seq) => {
+            for child in seq.children_mut() {
+                prefilter_component(child, mode);
+            }
+            return;
+        }
+        Component::Alternation(alt) => {
+            for branch in alt.children_mut() {
+                for child in branch.children_mut() {
+                    prefilter_component(child, mode);
+                }
+            }
+            return;
+        }
+        Component::Repeat(repeat) => {
+            widen_repeat(repeat);
+            prefilter_component(repeat.sub_component_mut(), mode);
+            return;
+        }
+        Component::Boundary(_) | Component::Class(_) | Component::WordBoundary(_) => return,
+        // The text matched by the group could be anything.
+        Component::BackReference(_) => dot_star(mode),
+        // Lookarounds are simply dropped.
+        Component::Assertion(_) => Component::Sequence(ComponentSequence::default()),
+        Component::AtomicGroup(group) => Component::Sequence(mem::take(&mut group.seq)),
+        // Whatever the condition, one of the branches or the empty string
+        // matches.
+        Component::CondReference(cond) => {
+            let mut seq = mem::take(&mut cond.seq);
+            if !seq.is_alternation() {
+                seq.add_alternation();
+                seq.finalize();
+            }
+            Component::Sequence(seq)
+        }
+    };
+    *c = replacement;
+    prefilter_component(c, mode);
+}
+
+/// Widens a repeat with a huge bound to an unbounded one, and makes it greedy
+/// if possessive.
+fn widen_repeat(repeat: &mut ComponentRepeat) {
+    if repeat.min > MAX_PREFILTER_REPEAT_BOUND
+        || repeat
+            .max
+            .map_or(false, |max| max > MAX_PREFILTER_REPEAT_BOUND)
+    {
+        repeat.min = repeat.min.min(MAX_PREFILTER_REPEAT_BOUND);
+        repeat.max = None;
+    }
+    if repeat.kind == RepeatType::Possessive {
+        repeat.kind = RepeatType::Greedy;
+    }
+}
+
+/// Returns a component matching any string, like `.*` in dotall mode.
+fn dot_star(mode: ParseMode) -> Component {
+    let mode = ParseMode {
+        dotall: true,
+        ..mode
+    };
+    let mut cc = get_component_class(mode);
+    cc.add_predefined(PredefinedClass::Any, false)
+        .expect("any character can be added to a class");
+    cc.finalize();
+    ComponentRepeat::new(cc.into(), 0, None, RepeatType::Greedy).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::dump::dump_tree;
+    use super::super::parse;
+    use super::*;
+
+    fn prefiltered(pattern: &str) -> String {
+        let mut mode = ParseMode::default();
+        let mut c = parse(pattern, &mut mode).expect("valid");
+        prefilter_tree(&mut c, mode);
+        let mut out = String::new();
+        dump_tree(&mut out, &c).unwrap();
+        out
+    }
+
+    #[test]
+    fn prefilter_unsupported() {
+        assert_eq!(prefiltered("(?=a)(?>b)\\1"), prefiltered("(?:)(?:b)(?s).*"));
+        assert_eq!(prefiltered("(?(1)a|b)"), prefiltered("(?:a|b)"));
+        assert_eq!(prefiltered("(?(?!x)a)"), prefiltered("(?:a|)"));
+    }
+
+    #[test]
+    fn prefilter_repeats() {
+        assert_eq!(prefiltered("a{2,500}b{101}"), prefiltered("a{2,}b{100,}"));
+        assert_eq!(prefiltered("a{2,100}b*+"), prefiltered("a{2,100}b*"));
+    }
+}
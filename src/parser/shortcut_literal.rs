@@ -1,8 +1,13 @@
 use super::ascii_component_class::AsciiComponentClass;
+use super::utf8_component_class::Utf8ComponentClass;
 use crate::compiler::ParsedExpression;
 use crate::nfagraph::Ng;
 use crate::parser::walk_component;
-use crate::parser::{ComponentAlternation, ComponentSequence, ConstComponentVisitor};
+use crate::parser::{
+    ComponentAlternation, ComponentAssertion, ComponentAtomicGroup, ComponentBackReference,
+    ComponentBoundary, ComponentCondReference, ComponentRepeat, ComponentSequence,
+    ComponentWordBoundary, ConstComponentVisitor,
+};
 use crate::util::Ue2Literal;
 use crate::CompileError;
 use std::convert::TryInto;
@@ -42,13 +47,63 @@ impl ConstComponentVisitor for ConstructLiteralVisitor {
         Err(NotLiteral {})
     }
 
+    fn pre_component_boundary(&mut self, _c: &ComponentBoundary) -> Result<(), Self::Error> {
+        Err(NotLiteral {})
+    }
+
+    fn pre_component_repeat(&mut self, _c: &ComponentRepeat) -> Result<(), Self::Error> {
+        Err(NotLiteral {})
+    }
+
+    fn pre_utf8_component_class(&mut self, _c: &Utf8ComponentClass) -> Result<(), Self::Error> {
+        Err(NotLiteral {})
+    }
+
+    fn pre_component_word_boundary(
+        &mut self,
+        _c: &ComponentWordBoundary,
+    ) -> Result<(), Self::Error> {
+        Err(NotLiteral {})
+    }
+
+    fn pre_component_assertion(&mut self, _c: &ComponentAssertion) -> Result<(), Self::Error> {
+        Err(NotLiteral {})
+    }
+
+    fn pre_component_atomic_group(&mut self, _c: &ComponentAtomicGroup) -> Result<(), Self::Error> {
+        Err(NotLiteral {})
+    }
+
+    fn pre_component_back_reference(
+        &mut self,
+        _c: &ComponentBackReference,
+    ) -> Result<(), Self::Error> {
+        Err(NotLiteral {})
+    }
+
+    fn pre_component_cond_reference(
+        &mut self,
+        _c: &ComponentCondReference,
+    ) -> Result<(), Self::Error> {
+        Err(NotLiteral {})
+    }
+
     fn during_ascii_component_class(&self, _c: &AsciiComponentClass) {}
     fn during_component_alternation(&self, _c: &ComponentAlternation) {}
     fn during_component_sequence(&self, _c: &ComponentSequence) {}
+    fn during_utf8_component_class(&self, _c: &Utf8ComponentClass) {}
 
     fn post_ascii_component_class(&mut self, _c: &AsciiComponentClass) {}
     fn post_component_alternation(&mut self, _c: &ComponentAlternation) {}
+    fn post_component_boundary(&mut self, _c: &ComponentBoundary) {}
+    fn post_component_repeat(&mut self, _c: &ComponentRepeat) {}
     fn post_component_sequence(&mut self, _c: &ComponentSequence) {}
+    fn post_utf8_component_class(&mut self, _c: &Utf8ComponentClass) {}
+    fn post_component_assertion(&mut self, _c: &ComponentAssertion) {}
+    fn post_component_atomic_group(&mut self, _c: &ComponentAtomicGroup) {}
+    fn post_component_back_reference(&mut self, _c: &ComponentBackReference) {}
+    fn post_component_cond_reference(&mut self, _c: &ComponentCondReference) {}
+    fn post_component_word_boundary(&mut self, _c: &ComponentWordBoundary) {}
 }
 
 /// Returns `true` if the literal expression could be added to Rose.
@@ -0,0 +1,108 @@
+use super::ascii_component_class::AsciiComponentClass;
+use super::utf8_component_class::Utf8ComponentClass;
+use super::{
+    walk_component, AssertionDir, Component, ComponentAlternation, ComponentAssertion,
+    ComponentAtomicGroup, ComponentBackReference, ComponentBoundary, ComponentCondReference,
+    ComponentRepeat, ComponentSequence, ComponentWordBoundary, ConstComponentVisitor, RepeatType,
+};
+use crate::util::compile_error::{CompileError, ErrorKind};
+
+/// Builds a parse error for a construct at offset `loc` in the pattern.
+fn located(why: &str, loc: usize) -> CompileError {
+    CompileError::new(ErrorKind::LocatedParse, format!("{} at index {}", why, loc))
+}
+
+/// Visitor rejecting constructs that are parsed but cannot be compiled.
+struct UnsupportedVisitor {}
+
+impl ConstComponentVisitor for UnsupportedVisitor {
+    type Error = CompileError;
+
+    fn pre_component_repeat(&mut self, c: &ComponentRepeat) -> Result<(), Self::Error> {
+        if c.kind == RepeatType::Possessive {
+            return Err(CompileError::new(
+                ErrorKind::Parse,
+                "Possessive quantifiers are not supported.",
+            ));
+        }
+        Ok(())
+    }
+
+    fn pre_component_assertion(&mut self, c: &ComponentAssertion) -> Result<(), Self::Error> {
+        let kind = match c.dir {
+            AssertionDir::Lookahead => "Lookahead",
+            AssertionDir::Lookbehind => "Lookbehind",
+        };
+        Err(located(
+            &format!("{} assertions are not supported", kind),
+            c.loc,
+        ))
+    }
+
+    fn pre_component_atomic_group(&mut self, c: &ComponentAtomicGroup) -> Result<(), Self::Error> {
+        Err(located("Atomic groups are not supported", c.loc))
+    }
+
+    fn pre_component_back_reference(
+        &mut self,
+        c: &ComponentBackReference,
+    ) -> Result<(), Self::Error> {
+        Err(located("Backreferences are not supported", c.loc))
+    }
+
+    fn pre_component_cond_reference(
+        &mut self,
+        c: &ComponentCondReference,
+    ) -> Result<(), Self::Error> {
+        Err(located("Conditional subpatterns are not supported", c.loc))
+    }
+
+    fn pre_ascii_component_class(&mut self, _c: &AsciiComponentClass) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn pre_component_alternation(&mut self, _c: &ComponentAlternation) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn pre_component_boundary(&mut self, _c: &ComponentBoundary) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn pre_component_sequence(&mut self, _c: &ComponentSequence) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn pre_utf8_component_class(&mut self, _c: &Utf8ComponentClass) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn pre_component_word_boundary(
+        &mut self,
+        _c: &ComponentWordBoundary,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn during_ascii_component_class(&self, _c: &AsciiComponentClass) {}
+    fn during_component_alternation(&self, _c: &ComponentAlternation) {}
+    fn during_component_sequence(&self, _c: &ComponentSequence) {}
+    fn during_utf8_component_class(&self, _c: &Utf8ComponentClass) {}
+
+    fn post_ascii_component_class(&mut self, _c: &AsciiComponentClass) {}
+    fn post_component_alternation(&mut self, _c: &ComponentAlternation) {}
+    fn post_component_boundary(&mut self, _c: &ComponentBoundary) {}
+    fn post_component_repeat(&mut self, _c: &ComponentRepeat) {}
+    fn post_component_sequence(&mut self, _c: &ComponentSequence) {}
+    fn post_utf8_component_class(&mut self, _c: &Utf8ComponentClass) {}
+    fn post_component_assertion(&mut self, _c: &ComponentAssertion) {}
+    fn post_component_atomic_group(&mut self, _c: &ComponentAtomicGroup) {}
+    fn post_component_back_reference(&mut self, _c: &ComponentBackReference) {}
+    fn post_component_cond_reference(&mut self, _c: &ComponentCondReference) {}
+    fn post_component_word_boundary(&mut self, _c: &ComponentWordBoundary) {}
+}
+
+/// Returns an error if the tree contains a construct we do not support.
+pub(crate) fn check_unsupported(root: &Component) -> Result<(), CompileError> {
+    walk_component(&mut UnsupportedVisitor {}, root)
+}
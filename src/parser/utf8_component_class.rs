@@ -0,0 +1,432 @@
+use super::component_class::{get_predefined_char_reach, PredefinedClass};
+use super::{
+    ConstComponentVisitor, GlushkovBuildState, ParseMode, PosFlags, Position, PositionInfo,
+};
+use crate::util::compile_error::{CompileError, ErrorKind};
+use crate::util::{
+    ucp_property, ucp_word_chars, utf8_sequences, CharReach, CodePointSet, MAX_UNICODE,
+};
+use std::collections::HashMap;
+
+/// Horizontal space characters, matched by `\h`.
+const HORZ_SPACES: &[(u32, u32)] = &[
+    (0x09, 0x09),
+    (0x20, 0x20),
+    (0xa0, 0xa0),
+    (0x1680, 0x1680),
+    (0x180e, 0x180e),
+    (0x2000, 0x200a),
+    (0x202f, 0x202f),
+    (0x205f, 0x205f),
+    (0x3000, 0x3000),
+];
+
+/// Vertical space characters, matched by `\v`.
+const VERT_SPACES: &[(u32, u32)] = &[(0x0a, 0x0d), (0x85, 0x85), (0x2028, 0x2029)];
+
+/// Returns the code points with any of the given Unicode properties.
+fn ucp(names: &[&str]) -> CodePointSet {
+    let mut cps = CodePointSet::default();
+    for name in names {
+        cps |= &ucp_property(name).expect("known Unicode property");
+    }
+    cps
+}
+
+/// Returns the code points in the given class.
+///
+/// In UCP mode, classes use their Unicode definition rather than the ASCII
+/// one.
+fn get_predefined_code_point_set(c: PredefinedClass, mode: ParseMode) -> CodePointSet {
+    match c {
+        PredefinedClass::Any => {
+            let mut cps = CodePointSet::default();
+            cps.set_range(0, MAX_UNICODE);
+            if !mode.dotall {
+                cps.unset(u32::from(b'\n'));
+            }
+            cps
+        }
+        PredefinedClass::Horz => CodePointSet::from_ranges(HORZ_SPACES),
+        PredefinedClass::Vert => CodePointSet::from_ranges(VERT_SPACES),
+        PredefinedClass::Alnum if mode.ucp => ucp(&["L", "N"]),
+        PredefinedClass::Alpha if mode.ucp => ucp(&["L"]),
+        PredefinedClass::Blank if mode.ucp => CodePointSet::from_ranges(HORZ_SPACES),
+        PredefinedClass::Digit if mode.ucp => ucp(&["Nd"]),
+        PredefinedClass::Lower | PredefinedClass::Upper if mode.ucp && mode.caseless => {
+            ucp(&["L&"])
+        }
+        PredefinedClass::Lower if mode.ucp => ucp(&["Ll"]),
+        PredefinedClass::Upper if mode.ucp => ucp(&["Lu"]),
+        PredefinedClass::Space if mode.ucp => {
+            let mut cps = ucp(&["Z"]);
+            cps.set_range(0x09, 0x0d);
+            cps
+        }
+        PredefinedClass::Word if mode.ucp => ucp_word_chars(),
+        _ => {
+            let mut cps = CodePointSet::default();
+            let cr = get_predefined_char_reach(c, mode);
+            let mut next = cr.find_first();
+            while let Some(b) = next {
+                cps.set(u32::from(b));
+                next = cr.find_next(b);
+            }
+            cps
+        }
+    }
+}
+
+/// Byte sequences, each given as a range per byte.
+pub(crate) type ByteSequences = Vec<Vec<(u8, u8)>>;
+
+/// Minimal automaton over the bytes of UTF-8 encodings. State 0 accepts,
+/// every other state has the transitions leading out of it.
+#[derive(Default)]
+pub(crate) struct Utf8Dfa {
+    pub(crate) states: Vec<Vec<(CharReach, usize)>>,
+    /// States by their transitions, so that equivalent states are shared.
+    ids: HashMap<Vec<(CharReach, usize)>, usize>,
+    /// States already built for a set of encoding suffixes.
+    built: HashMap<ByteSequences, usize>,
+}
+
+impl Utf8Dfa {
+    pub(crate) const ACCEPT: usize = 0;
+
+    pub(crate) fn new() -> Self {
+        let mut dfa = Self::default();
+        dfa.states.push(Vec::new());
+        dfa
+    }
+
+    /// Returns the state matching exactly the byte sequences in `seqs`.
+    pub(crate) fn build(&mut self, seqs: ByteSequences) -> usize {
+        if seqs.iter().all(Vec::is_empty) {
+            return Self::ACCEPT;
+        }
+        if let Some(&id) = self.built.get(&seqs) {
+            return id;
+        }
+
+        // Group the bytes by the suffixes they may be followed by.
+        let mut by_tails: Vec<(ByteSequences, CharReach)> = Vec::new();
+        for b in 0..=255 {
+            let tails: Vec<_> = seqs
+                .iter()
+                .filter(|seq| seq[0].0 <= b && b <= seq[0].1)
+                .map(|seq| seq[1..].to_vec())
+                .collect();
+            if tails.is_empty() {
+                continue;
+            }
+            match by_tails.iter_mut().find(|(t, _)| *t == tails) {
+                Some((_, cr)) => cr.set(b),
+                None => by_tails.push((tails, CharReach::from_char(b))),
+            }
+        }
+
+        let mut trans: Vec<(CharReach, usize)> = Vec::new();
+        for (tails, cr) in by_tails {
+            let next = self.build(tails);
+            match trans.iter_mut().find(|(_, n)| *n == next) {
+                Some((c, _)) => *c |= cr,
+                None => trans.push((cr, next)),
+            }
+        }
+        trans.sort();
+
+        let id = match self.ids.get(&trans) {
+            Some(&id) => id,
+            None => {
+                self.states.push(trans.clone());
+                self.ids.insert(trans, self.states.len() - 1);
+                self.states.len() - 1
+            }
+        };
+        self.built.insert(seqs, id);
+        id
+    }
+}
+
+/// Position of the automaton matching a UTF-8 class.
+#[derive(Clone, Debug)]
+struct Utf8Position {
+    position: Position,
+    /// State the position leads to, or `None` if it ends a character.
+    next: Option<usize>,
+}
+
+/// Character class in UTF-8 mode, made of whole code points.
+///
+/// The class is matched by the minimal automaton over the bytes encoding its
+/// code points. Each transition of the automaton becomes a position, shared by
+/// the transitions on the same bytes to the same state.
+#[derive(Clone, Debug)]
+pub(crate) struct Utf8ComponentClass {
+    mode: ParseMode,
+    pub(in crate::parser) cps: CodePointSet,
+
+    /// Whether the class is negated, applied when it is finalized.
+    negate: bool,
+    /// Set after a dash that may turn into a range.
+    in_cand_range: bool,
+    /// Last character added, which may start a range.
+    range_start: Option<char>,
+    finalized: bool,
+
+    /// Positions of the automaton, built when positions are noted.
+    positions: Vec<Utf8Position>,
+    /// Indices of the positions leading out of each state of the automaton,
+    /// starting with the initial state.
+    state_positions: Vec<Vec<usize>>,
+}
+
+impl Utf8ComponentClass {
+    pub(in crate::parser) fn new(mode: ParseMode) -> Self {
+        Self {
+            mode,
+            cps: CodePointSet::default(),
+            negate: false,
+            in_cand_range: false,
+            range_start: None,
+            finalized: false,
+            positions: Vec::new(),
+            state_positions: Vec::new(),
+        }
+    }
+
+    pub(in crate::parser) fn add(&mut self, c: char) -> Result<(), CompileError> {
+        if self.in_cand_range {
+            return self.create_range(c);
+        }
+
+        self.add_range(c, c);
+        self.range_start = Some(c);
+        Ok(())
+    }
+
+    pub(in crate::parser) fn add_predefined(
+        &mut self,
+        c: PredefinedClass,
+        negated: bool,
+    ) -> Result<(), CompileError> {
+        if self.in_cand_range {
+            // Can't form a range here.
+            return Err(CompileError::new(
+                ErrorKind::LocatedParse,
+                "Invalid range in character class",
+            ));
+        }
+
+        let mut pcps = get_predefined_code_point_set(c, self.mode);
+        if negated {
+            pcps.flip();
+        }
+        for &(lo, hi) in pcps.ranges() {
+            self.cps.set_range(lo, hi);
+        }
+        self.range_start = None;
+        Ok(())
+    }
+
+    /// Adds the code points of a Unicode property.
+    pub(in crate::parser) fn add_code_points(
+        &mut self,
+        cps: &CodePointSet,
+    ) -> Result<(), CompileError> {
+        if self.in_cand_range {
+            // Can't form a range here.
+            return Err(CompileError::new(
+                ErrorKind::LocatedParse,
+                "Invalid range in character class",
+            ));
+        }
+
+        self.cps |= cps;
+        self.range_start = None;
+        Ok(())
+    }
+
+    pub(in crate::parser) fn add_dash(&mut self) -> Result<(), CompileError> {
+        if !self.in_cand_range {
+            // This could be the start of a range.
+            if self.range_start.is_some() {
+                self.in_cand_range = true;
+                Ok(())
+            } else {
+                // No possible start character for range, this is just a
+                // literal.
+                self.add('-')
+            }
+        } else {
+            // Already creating a range, so this must be literal '-'.
+            self.create_range('-')
+        }
+    }
+
+    fn create_range(&mut self, to: char) -> Result<(), CompileError> {
+        let from = self.range_start.expect("range must have a start");
+        if from > to {
+            return Err(CompileError::new(
+                ErrorKind::LocatedParse,
+                "Range out of order in character class",
+            ));
+        }
+
+        self.in_cand_range = false;
+        self.add_range(from, to);
+        self.range_start = None;
+        Ok(())
+    }
+
+    /// Adds the code points from `from` to `to`, and their other cases if the
+    /// class is caseless.
+    fn add_range(&mut self, from: char, to: char) {
+        let mut ncps = CodePointSet::default();
+        ncps.set_range(u32::from(from), u32::from(to));
+        if self.mode.caseless {
+            ncps.make_caseless();
+        }
+        self.cps |= &ncps;
+    }
+
+    pub(in crate::parser) fn negate(&mut self) {
+        self.negate = true;
+    }
+
+    pub(in crate::parser) fn finalize(&mut self) {
+        if self.finalized {
+            return;
+        }
+
+        // Handle unclosed ranges, like '[a-]': in these cases the dash is a
+        // literal dash.
+        if self.in_cand_range {
+            self.cps.set(u32::from(b'-'));
+            self.in_cand_range = false;
+        }
+
+        if self.negate {
+            self.cps.flip();
+        }
+
+        self.finalized = true;
+    }
+
+    /// Returns the positions leading out of the given state.
+    fn out_of(&self, state: usize) -> Vec<PositionInfo> {
+        self.state_positions.get(state).map_or_else(Vec::new, |ps| {
+            ps.iter()
+                .map(|&i| self.positions[i].position.into())
+                .collect()
+        })
+    }
+
+    pub(in crate::parser) fn first(&self) -> Vec<PositionInfo> {
+        self.out_of(0)
+    }
+
+    pub(in crate::parser) fn last(&self) -> Vec<PositionInfo> {
+        self.positions
+            .iter()
+            .filter(|p| p.next.is_none())
+            .map(|p| p.position.into())
+            .collect()
+    }
+
+    pub(in crate::parser) fn note_positions(&mut self, bs: &mut GlushkovBuildState) {
+        // In UCP mode, word and non-word characters get positions of their
+        // own, so that word boundaries can tell them apart.
+        let parts = if self.mode.ucp {
+            let mut non_word = self.cps.clone();
+            non_word -= &ucp_word_chars();
+            let mut word = self.cps.clone();
+            word -= &non_word;
+            vec![
+                (word, PosFlags::WORD_CHAR),
+                (non_word, PosFlags::NONWORD_CHAR),
+            ]
+        } else {
+            vec![(self.cps.clone(), PosFlags::empty())]
+        };
+
+        let builder = bs.get_builder_mut();
+        self.positions.clear();
+        self.state_positions = vec![Vec::new()];
+        for (cps, flags) in parts {
+            let mut seqs = Vec::new();
+            for &(lo, hi) in cps.ranges() {
+                seqs.extend(utf8_sequences(lo, hi));
+            }
+            if seqs.is_empty() {
+                continue;
+            }
+            let mut dfa = Utf8Dfa::new();
+            let initial = dfa.build(seqs);
+
+            // Number the states reachable from the initial one, which is
+            // shared by all parts; the accept state has no positions leading
+            // out of it.
+            let base = self.state_positions.len();
+            let mut order = vec![initial];
+            let mut numbers: HashMap<usize, usize> = HashMap::new();
+            numbers.insert(initial, 0);
+            let mut i = 0;
+            while i < order.len() {
+                for &(_, next) in &dfa.states[order[i]] {
+                    if next != Utf8Dfa::ACCEPT && !numbers.contains_key(&next) {
+                        numbers.insert(next, base + order.len() - 1);
+                        order.push(next);
+                    }
+                }
+                i += 1;
+            }
+
+            let mut shared: HashMap<(CharReach, Option<usize>), usize> = HashMap::new();
+            for (i, &state) in order.iter().enumerate() {
+                let out: Vec<_> = dfa.states[state]
+                    .iter()
+                    .map(|&(cr, next)| {
+                        let next = numbers.get(&next).copied();
+                        *shared.entry((cr, next)).or_insert_with(|| {
+                            let position = builder.make_position();
+                            builder.add_char_reach(position, cr);
+                            builder.set_assert_flag(position, flags);
+                            self.positions.push(Utf8Position { position, next });
+                            self.positions.len() - 1
+                        })
+                    })
+                    .collect();
+                if i == 0 {
+                    self.state_positions[0].extend(out);
+                } else {
+                    self.state_positions.push(out);
+                }
+            }
+        }
+    }
+
+    pub(in crate::parser) fn build_follow_set(
+        &self,
+        bs: &mut GlushkovBuildState,
+        last_pos: &[PositionInfo],
+    ) {
+        bs.connect_regions(last_pos, &self.first());
+        for p in &self.positions {
+            if let Some(next) = p.next {
+                bs.connect_regions(&[p.position.into()], &self.out_of(next));
+            }
+        }
+    }
+}
+
+pub(in crate::parser) fn walk_utf8_component_class<V: ConstComponentVisitor>(
+    v: &mut V,
+    c: &Utf8ComponentClass,
+) -> Result<(), V::Error> {
+    v.pre_utf8_component_class(c)?;
+    v.during_utf8_component_class(c);
+    v.post_utf8_component_class(c);
+    Ok(())
+}
@@ -1,11 +1,16 @@
 mod block;
+mod program_runtime;
 mod rose_build;
 mod rose_build_bytecode;
 mod rose_build_resources;
 mod rose_internal;
+mod stream;
 
-pub(crate) use block::rose_block_exec;
+pub(crate) use block::{rose_block_exec, rose_vectored_exec};
 pub(crate) use rose_build::RoseBuild;
 use rose_build_bytecode::DerivedBoundaryReports;
 use rose_build_resources::RoseResources;
-pub(crate) use rose_internal::{RoseEngine, RoseRuntimeImpl};
+use rose_internal::RoseBoundaryReports;
+pub(crate) use rose_internal::RoseEngine;
+pub use rose_internal::RoseRuntimeImpl;
+pub(crate) use stream::{rose_stream_eod_exec, rose_stream_exec, RoseState};
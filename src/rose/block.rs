@@ -1,3 +1,49 @@
+use super::stream::{rose_stream_eod_exec, rose_stream_exec};
 use crate::rose::RoseEngine;
+use crate::runtime::Matching;
+use crate::scratch::Scratch;
+use std::mem;
 
-pub(crate) fn rose_block_exec(_rose: &RoseEngine) {}
+pub(crate) fn rose_block_exec<F>(
+    rose: &RoseEngine,
+    scratch: &mut Scratch,
+    data: &[u8],
+    on_match: &mut F,
+) -> Matching
+where
+    F: FnMut(u32, u64, u64) -> Matching,
+{
+    rose_vectored_exec(rose, scratch, &[data], on_match)
+}
+
+/// Scans the concatenation of `data` as a single block.
+pub(crate) fn rose_vectored_exec<F>(
+    rose: &RoseEngine,
+    scratch: &mut Scratch,
+    data: &[&[u8]],
+    on_match: &mut F,
+) -> Matching
+where
+    F: FnMut(u32, u64, u64) -> Matching,
+{
+    // A block is a stream made of a single write; a vector is one made of a
+    // write per buffer.
+    let mut state = mem::take(&mut scratch.state);
+    state.clear();
+
+    let mut offset = 0;
+    let mut status = Matching::Continue;
+    for buf in data {
+        status = rose_stream_exec(rose, &mut state, scratch, buf, offset, on_match);
+        if let Matching::Terminate = status {
+            break;
+        }
+        offset += buf.len() as u64;
+    }
+    if let Matching::Continue = status {
+        status = rose_stream_eod_exec(rose, &mut state, scratch, offset, on_match);
+    }
+
+    scratch.state = state;
+    status
+}
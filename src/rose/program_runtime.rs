@@ -0,0 +1,169 @@
+use super::stream::ExhaustedKeys;
+use crate::rose::RoseEngine;
+use crate::runtime::Matching;
+use crate::util::{ReportId, ReportType};
+use bit_vec::BitVec;
+use std::mem;
+
+/// Returns whether no pattern can match anymore, given the exhaustion keys in
+/// `exhausted`.
+pub(crate) fn can_stop_matching(rose: &RoseEngine, exhausted: &ExhaustedKeys) -> bool {
+    rose.global_exhaust && exhausted.count() == rose.num_ekeys
+}
+
+/// Matches raised but not delivered yet.
+///
+/// Some reports are raised a few bytes after the end of their match, so
+/// matches wait here until no match ending earlier can be raised, and are
+/// then delivered in order of end offset.
+#[derive(Clone, Default)]
+pub(crate) struct MatchQueue {
+    /// Matches waiting, as `(to, external ID, internal report)`.
+    pending: Vec<(u64, u32, ReportId)>,
+    /// The last match delivered, as `(to, external ID)`.
+    last: Option<(u64, u32)>,
+}
+
+impl MatchQueue {
+    pub(crate) fn clear(&mut self) {
+        self.pending.clear();
+        self.last = None;
+    }
+
+    /// Returns the number of bytes allocated on the heap for the queue.
+    pub(crate) fn heap_size(&self) -> usize {
+        self.pending.capacity() * mem::size_of::<(u64, u32, ReportId)>()
+    }
+}
+
+/// Queues the matches of the internal reports raised at stream offset `end`,
+/// and delivers those no match raised later can come before.
+pub(crate) fn rose_deliver_reports<F>(
+    rose: &RoseEngine,
+    exhausted: &mut ExhaustedKeys,
+    logical: &mut BitVec,
+    queue: &mut MatchQueue,
+    reports: &[ReportId],
+    end: u64,
+    on_match: &mut F,
+) -> Matching
+where
+    F: FnMut(u32, u64, u64) -> Matching,
+{
+    for &id in reports {
+        let ir = &rose.reports[id as usize];
+        #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+        let to = (end as i64 + i64::from(ir.offset_adjust)) as u64;
+        queue.pending.push((to, ir.onmatch, id));
+    }
+    // Reports raised after `end` match at `end + 1 - max_delay` or later.
+    let horizon = (end + 1).saturating_sub(u64::from(rose.max_delay));
+    deliver_queue(rose, exhausted, logical, queue, horizon, on_match)
+}
+
+/// Delivers every match left in `queue`, at the end of data.
+pub(crate) fn rose_flush_reports<F>(
+    rose: &RoseEngine,
+    exhausted: &mut ExhaustedKeys,
+    logical: &mut BitVec,
+    queue: &mut MatchQueue,
+    on_match: &mut F,
+) -> Matching
+where
+    F: FnMut(u32, u64, u64) -> Matching,
+{
+    deliver_queue(rose, exhausted, logical, queue, u64::MAX, on_match)
+}
+
+/// Delivers the matches of `queue` ending before `horizon` to the user's
+/// callback, and records the matches of the sub-expressions of combinations
+/// in `logical`.
+///
+/// Reports whose exhaustion key is in `exhausted` are dropped, and those of
+/// the others are added to it.
+fn deliver_queue<F>(
+    rose: &RoseEngine,
+    exhausted: &mut ExhaustedKeys,
+    logical: &mut BitVec,
+    queue: &mut MatchQueue,
+    horizon: u64,
+    on_match: &mut F,
+) -> Matching
+where
+    F: FnMut(u32, u64, u64) -> Matching,
+{
+    // Deliver in order of end offset, with duplicates next to each other.
+    queue.pending.sort_unstable();
+    let ready = queue
+        .pending
+        .iter()
+        .position(|&(to, _, _)| to >= horizon)
+        .unwrap_or(queue.pending.len());
+
+    let mut status = Matching::Continue;
+    for &(to, onmatch, id) in &queue.pending[..ready] {
+        let ir = &rose.reports[id as usize];
+        if let Some(ekey) = ir.ekey {
+            if exhausted.get(ekey) {
+                continue;
+            }
+            exhausted.set(ekey);
+        }
+        if let Some(lkey) = ir.lkey {
+            logical.set(lkey as usize, true);
+        }
+        if ir.quiet {
+            continue;
+        }
+
+        // Different internal reports may describe the same external match.
+        if queue.last == Some((to, onmatch)) {
+            continue;
+        }
+        queue.last = Some((to, onmatch));
+
+        let from = match ir.kind {
+            ReportType::ExternalCallback => 0,
+            ReportType::ExternalCallbackSomRel => to.saturating_sub(ir.som_distance),
+        };
+        if let Matching::Terminate = on_match(onmatch, from, to) {
+            status = Matching::Terminate;
+            break;
+        }
+    }
+    queue.pending.drain(..ready);
+    status
+}
+
+/// Reports the logical combinations holding at the end of data, which is at
+/// stream offset `end`, given the sub-expressions matched in `logical`.
+///
+/// `ids` is scratch space.
+pub(crate) fn rose_flush_combinations<F>(
+    rose: &RoseEngine,
+    logical: &BitVec,
+    ids: &mut Vec<ReportId>,
+    end: u64,
+    on_match: &mut F,
+) -> Matching
+where
+    F: FnMut(u32, u64, u64) -> Matching,
+{
+    ids.clear();
+    ids.extend(
+        rose.combinations
+            .iter()
+            .filter(|comb| !comb.quiet && comb.eval(logical))
+            .map(|comb| comb.id),
+    );
+    // Several combinations may share an ID.
+    ids.sort_unstable();
+    ids.dedup();
+
+    for &id in ids.iter() {
+        if let Matching::Terminate = on_match(id, 0, end) {
+            return Matching::Terminate;
+        }
+    }
+    Matching::Continue
+}
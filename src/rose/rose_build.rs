@@ -1,33 +1,72 @@
-use super::rose_build_bytecode::{pick_runtime_impl, BuildContext};
+use super::rose_build_bytecode::{make_boundary_programs, pick_runtime_impl, BuildContext};
+use super::rose_internal::max_delay;
+use crate::nfagraph::{construct_nfa, make_literal_graph, NgHolder};
 use crate::rose::RoseEngine;
-use crate::util::{CompileContext, ReportId, Ue2Literal};
+use crate::util::{BoundaryReports, CompileContext, Depth, ReportId, ReportManager, Ue2Literal};
 use crate::Mode;
 use std::collections::HashSet;
+use std::ptr::NonNull;
+
+/// A literal added to Rose, with the reports it raises.
+pub(super) struct RoseLiteral {
+    pub(super) lit: Ue2Literal,
+    pub(super) anchored: bool,
+    pub(super) eod: bool,
+    pub(super) reports: HashSet<ReportId>,
+}
 
 pub(crate) struct RoseBuild<'a> {
     pub(crate) cc: &'a CompileContext,
+    pub(crate) rm: NonNull<ReportManager<'a>>,
+    /// Reports raised by vacuous patterns at the boundaries of the data.
+    pub(crate) boundary: NonNull<BoundaryReports>,
     pub(crate) has_som: bool,
+
+    /// Literals to match.
+    pub(super) literals: Vec<RoseLiteral>,
+
+    /// Engines run alongside the literals, raising their own reports.
+    pub(super) outfixes: Vec<NgHolder>,
 }
 
 impl<'a> RoseBuild<'a> {
-    #[allow(clippy::needless_return)]
+    pub(crate) fn new(cc: &'a CompileContext) -> Self {
+        Self {
+            cc,
+            rm: NonNull::dangling(),
+            boundary: NonNull::dangling(),
+            has_som: false,
+            literals: Vec::new(),
+            outfixes: Vec::new(),
+        }
+    }
+
     pub(crate) fn add(
         &mut self,
-        _anchored: bool,
-        _eod: bool,
-        _lit: &Ue2Literal,
-        _reports: &HashSet<ReportId>,
+        anchored: bool,
+        eod: bool,
+        lit: &Ue2Literal,
+        reports: &HashSet<ReportId>,
     ) {
-        if self.cc.grey.flood_as_puffette {
-            return;
-        }
+        debug_assert!(!reports.is_empty());
+        self.literals.push(RoseLiteral {
+            lit: lit.clone(),
+            anchored,
+            eod,
+            reports: reports.clone(),
+        });
+    }
+
+    /// Adds a graph to be run as an outfix engine.
+    pub(crate) fn add_outfix(&mut self, g: NgHolder) {
+        self.outfixes.push(g);
     }
 
-    pub(crate) fn build_rose(&self) -> RoseEngine {
-        self.build_final_engine()
+    pub(crate) fn build_rose(&self, min_width: Depth) -> RoseEngine {
+        self.build_final_engine(min_width)
     }
 
-    fn build_final_engine(&self) -> RoseEngine {
+    fn build_final_engine(&self, min_width: Depth) -> RoseEngine {
         // Set scanning mode.
         let mode = if self.cc.streaming {
             if self.cc.vectored {
@@ -39,13 +78,58 @@ impl<'a> RoseBuild<'a> {
             Mode::Block
         };
 
-        let _dboundary = super::DerivedBoundaryReports {};
+        let boundary = unsafe { self.boundary.as_ref() };
+        let dboundary = super::DerivedBoundaryReports::new(boundary);
 
-        let bc = BuildContext::default();
+        let mut bc = BuildContext::default();
+        for lit in &self.literals {
+            if lit.anchored {
+                bc.resources.has_anchored = true;
+            } else {
+                bc.resources.has_floating = true;
+            }
+            if lit.eod {
+                bc.resources.has_eod = true;
+            }
+        }
+        bc.resources.has_literals = !self.literals.is_empty();
+        bc.resources.has_outfixes = !self.outfixes.is_empty();
+
+        let literal_graphs: Vec<NgHolder> = self
+            .literals
+            .iter()
+            .map(|l| make_literal_graph(&l.lit, l.anchored, l.eod, &l.reports))
+            .collect();
+        let nfa = construct_nfa(literal_graphs.iter().chain(self.outfixes.iter()));
+
+        let rm = unsafe { self.rm.as_ref() };
+        // Sub-expressions of combinations record their matches under their
+        // logical key.
+        let mut reports = rm.reports().to_vec();
+        for ir in &mut reports {
+            ir.lkey = rm.pl.logical_key(ir.onmatch);
+        }
 
         RoseEngine {
             runtime_impl: pick_runtime_impl(self, &bc.resources),
             mode,
+            nfa,
+            max_delay: max_delay(&reports),
+            reports,
+            num_ekeys: rm.num_ekeys(),
+            // Combinations are evaluated at end of data whatever matched.
+            global_exhaust: rm.global_exhaust()
+                && rm.num_ekeys() > 0
+                && rm.pl.combinations().next().is_none(),
+            num_lkeys: rm.pl.num_logical_keys(),
+            combinations: rm.pl.combinations().cloned().collect(),
+            boundary: make_boundary_programs(boundary, &dboundary),
+            // Boundary reports match data of any length.
+            min_width: if boundary.is_empty() {
+                min_width.value().unwrap_or(u32::MAX)
+            } else {
+                0
+            },
         }
     }
 }
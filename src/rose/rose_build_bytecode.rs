@@ -1,5 +1,6 @@
-use super::{RoseBuild, RoseResources, RoseRuntimeImpl};
-use crate::util::CompileContext;
+use super::{RoseBoundaryReports, RoseBuild, RoseResources, RoseRuntimeImpl};
+use crate::util::{BoundaryReports, CompileContext, ReportId};
+use std::collections::HashSet;
 
 #[derive(Default)]
 pub(super) struct BuildContext {
@@ -43,8 +44,8 @@ fn is_pure_floating(resources: &RoseResources, cc: &CompileContext) -> bool {
     true
 }
 
-fn is_single_outfix(_tbi: &RoseBuild) -> bool {
-    false
+fn is_single_outfix(tbi: &RoseBuild) -> bool {
+    tbi.literals.is_empty() && tbi.outfixes.len() == 1
 }
 
 pub(super) fn pick_runtime_impl(build: &RoseBuild, resources: &RoseResources) -> RoseRuntimeImpl {
@@ -59,4 +60,37 @@ pub(super) fn pick_runtime_impl(build: &RoseBuild, resources: &RoseResources) ->
     RoseRuntimeImpl::FullRose
 }
 
-pub(super) struct DerivedBoundaryReports {}
+pub(super) struct DerivedBoundaryReports {
+    /// Set of internal reports to fire unconditionally at offset 0 if it is
+    /// eod, including those firing at offset 0 or at eod whatever the length.
+    pub(super) report_at_0_eod_full: HashSet<ReportId>,
+}
+
+impl DerivedBoundaryReports {
+    pub(super) fn new(boundary: &BoundaryReports) -> Self {
+        let mut report_at_0_eod_full = boundary.report_at_0_eod.clone();
+        report_at_0_eod_full.extend(&boundary.report_at_0);
+        report_at_0_eod_full.extend(&boundary.report_at_eod);
+        Self {
+            report_at_0_eod_full,
+        }
+    }
+}
+
+/// Returns the given reports, sorted.
+fn make_boundary_program(reports: &HashSet<ReportId>) -> Vec<ReportId> {
+    let mut program: Vec<_> = reports.iter().copied().collect();
+    program.sort_unstable();
+    program
+}
+
+pub(super) fn make_boundary_programs(
+    boundary: &BoundaryReports,
+    dboundary: &DerivedBoundaryReports,
+) -> RoseBoundaryReports {
+    RoseBoundaryReports {
+        report_eod_offset: make_boundary_program(&boundary.report_at_eod),
+        report_zero_offset: make_boundary_program(&boundary.report_at_0),
+        report_zero_eod_offset: make_boundary_program(&dboundary.report_at_0_eod_full),
+    }
+}
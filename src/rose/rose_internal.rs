@@ -1,16 +1,233 @@
-use crate::Mode;
+use crate::nfa::Nfa;
+use crate::util::serialize::{CorruptError, Reader, Writer};
+use crate::util::{CombInfo, LogicalOp, Report, ReportId};
+use crate::{Mode, SomHorizon};
+use std::mem;
 
-pub(crate) enum RoseRuntimeImpl {
+/// Strategy used by a database at scan time.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RoseRuntimeImpl {
+    /// Full engine, running literal matchers and automata together.
     FullRose,
+    /// Only literals are matched.
     PureLiteral,
+    /// A single automaton runs on its own.
     SingleOutfix,
 }
 
+/// Reports raised unconditionally at the boundaries of the data, by patterns
+/// matching without consuming any.
+#[derive(Default)]
+pub(crate) struct RoseBoundaryReports {
+    /// Reports to fire at end of data, unless it is at offset 0.
+    pub(crate) report_eod_offset: Vec<ReportId>,
+    /// Reports to fire at offset 0, unless it is end of data.
+    pub(crate) report_zero_offset: Vec<ReportId>,
+    /// Reports to fire at offset 0 if it is end of data.
+    pub(crate) report_zero_eod_offset: Vec<ReportId>,
+}
+
+impl RoseBoundaryReports {
+    fn lists(&self) -> [&Vec<ReportId>; 3] {
+        [
+            &self.report_eod_offset,
+            &self.report_zero_offset,
+            &self.report_zero_eod_offset,
+        ]
+    }
+
+    fn heap_size(&self) -> usize {
+        self.lists()
+            .iter()
+            .map(|list| list.capacity() * mem::size_of::<ReportId>())
+            .sum()
+    }
+
+    fn serialize(&self, w: &mut Writer) {
+        for list in &self.lists() {
+            w.put_u32_slice(list);
+        }
+    }
+
+    /// Reads boundary reports referring to the first `num_reports` internal
+    /// reports.
+    fn deserialize(r: &mut Reader, num_reports: usize) -> Result<Self, CorruptError> {
+        let mut list = || -> Result<Vec<ReportId>, CorruptError> {
+            let ids = r.u32_vec()?;
+            if ids.iter().any(|&id| id as usize >= num_reports) {
+                return Err(CorruptError);
+            }
+            Ok(ids)
+        };
+        Ok(Self {
+            report_eod_offset: list()?,
+            report_zero_offset: list()?,
+            report_zero_eod_offset: list()?,
+        })
+    }
+}
+
 pub(crate) struct RoseEngine {
     /// Can we just run the floating table or a single outfix or do we need a
     /// full rose?
     pub(crate) runtime_impl: RoseRuntimeImpl,
     /// Scanning mode.
-    #[allow(dead_code)]
-    pub(super) mode: Mode,
+    pub(crate) mode: Mode,
+    /// Automaton matching every pattern in the database.
+    pub(crate) nfa: Nfa,
+    /// Internal reports, indexed by ID.
+    pub(crate) reports: Vec<Report>,
+    /// Largest number of bytes a report is raised after the end of its
+    /// match. Not serialized, as it follows from the reports.
+    pub(crate) max_delay: u32,
+    /// Number of exhaustion keys given to single-match patterns.
+    pub(crate) num_ekeys: u32,
+    /// Whether scanning can stop once every exhaustion key is exhausted,
+    /// because every pattern is single-match.
+    pub(crate) global_exhaust: bool,
+    /// Number of logical keys given to the sub-expressions of combinations.
+    pub(crate) num_lkeys: u32,
+    /// Logical combinations, evaluated at end of data.
+    pub(crate) combinations: Vec<CombInfo>,
+    /// Reports raised at the boundaries of the data.
+    pub(crate) boundary: RoseBoundaryReports,
+    /// Length of the shortest data that can produce a match, or `u32::MAX`
+    /// if no data can.
+    pub(crate) min_width: u32,
+}
+
+impl RoseEngine {
+    /// Returns the number of bytes used by the engine, including the memory
+    /// it owns.
+    pub(crate) fn size(&self) -> usize {
+        mem::size_of::<Self>()
+            + self.reports.capacity() * mem::size_of::<Report>()
+            + self
+                .combinations
+                .iter()
+                .map(|comb| {
+                    mem::size_of::<CombInfo>() + comb.ops.capacity() * mem::size_of::<LogicalOp>()
+                })
+                .sum::<usize>()
+            + self.boundary.heap_size()
+            + self.nfa.heap_size()
+    }
+
+    pub(crate) fn serialize(&self, w: &mut Writer) {
+        w.put_u8(match self.runtime_impl {
+            RoseRuntimeImpl::FullRose => 0,
+            RoseRuntimeImpl::PureLiteral => 1,
+            RoseRuntimeImpl::SingleOutfix => 2,
+        });
+        w.put_u8(match self.mode {
+            Mode::Block => 0,
+            Mode::Stream(None) => 1,
+            Mode::Stream(Some(SomHorizon::Large)) => 2,
+            Mode::Stream(Some(SomHorizon::Medium)) => 3,
+            Mode::Stream(Some(SomHorizon::Small)) => 4,
+            Mode::Vectored => 5,
+        });
+        w.put_len(self.reports.len());
+        for ir in &self.reports {
+            ir.serialize(w);
+        }
+        w.put_u32(self.num_ekeys);
+        w.put_bool(self.global_exhaust);
+        w.put_u32(self.num_lkeys);
+        w.put_len(self.combinations.len());
+        for comb in &self.combinations {
+            comb.serialize(w);
+        }
+        self.boundary.serialize(w);
+        self.nfa.serialize(w);
+        w.put_u32(self.min_width);
+    }
+
+    pub(crate) fn deserialize(r: &mut Reader) -> Result<Self, CorruptError> {
+        let runtime_impl = match r.u8()? {
+            0 => RoseRuntimeImpl::FullRose,
+            1 => RoseRuntimeImpl::PureLiteral,
+            2 => RoseRuntimeImpl::SingleOutfix,
+            _ => return Err(CorruptError),
+        };
+        let mode = match r.u8()? {
+            0 => Mode::Block,
+            1 => Mode::Stream(None),
+            2 => Mode::Stream(Some(SomHorizon::Large)),
+            3 => Mode::Stream(Some(SomHorizon::Medium)),
+            4 => Mode::Stream(Some(SomHorizon::Small)),
+            5 => Mode::Vectored,
+            _ => return Err(CorruptError),
+        };
+        let num_reports = r.len(1)?;
+        let reports = (0..num_reports)
+            .map(|_| Report::deserialize(r))
+            .collect::<Result<Vec<_>, _>>()?;
+        if reports.iter().any(|ir| delay(ir) > MAX_DELAY) {
+            return Err(CorruptError);
+        }
+        let num_ekeys = r.u32()?;
+        let global_exhaust = r.bool()?;
+        let num_lkeys = r.u32()?;
+        // Every exhaustion key belongs to a report; the key counts size the
+        // state of each scan, so they must not be trusted blindly.
+        if num_ekeys as usize > reports.len()
+            || reports.iter().any(|ir| {
+                ir.ekey.map_or(false, |ekey| ekey >= num_ekeys)
+                    || ir.lkey.map_or(false, |lkey| lkey >= num_lkeys)
+            })
+        {
+            return Err(CorruptError);
+        }
+        let num_combinations = r.len(1)?;
+        let combinations = (0..num_combinations)
+            .map(|_| CombInfo::deserialize(r, num_lkeys))
+            .collect::<Result<Vec<_>, _>>()?;
+        // As does every logical key to an operand of a combination.
+        let num_operands: usize = combinations
+            .iter()
+            .map(|comb| {
+                comb.ops
+                    .iter()
+                    .filter(|op| matches!(op, LogicalOp::Key(_)))
+                    .count()
+            })
+            .sum();
+        if num_lkeys as usize > num_operands {
+            return Err(CorruptError);
+        }
+        let boundary = RoseBoundaryReports::deserialize(r, reports.len())?;
+        let nfa = Nfa::deserialize(r, reports.len())?;
+        let min_width = r.u32()?;
+        Ok(Self {
+            runtime_impl,
+            mode,
+            nfa,
+            max_delay: max_delay(&reports),
+            reports,
+            num_ekeys,
+            global_exhaust,
+            num_lkeys,
+            combinations,
+            boundary,
+            min_width,
+        })
+    }
+}
+
+/// Largest number of bytes a report may be raised after the end of its match:
+/// the length of the longest UTF-8 character, looked at past the end.
+const MAX_DELAY: u32 = 4;
+
+/// Returns the number of bytes `ir` is raised after the end of its match.
+fn delay(ir: &Report) -> u32 {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let delay = (-i64::from(ir.offset_adjust.min(0))) as u32;
+    delay
+}
+
+/// Returns the largest number of bytes one of `reports` is raised after the
+/// end of its match.
+pub(crate) fn max_delay(reports: &[Report]) -> u32 {
+    reports.iter().map(delay).max().unwrap_or(0)
 }
@@ -0,0 +1,212 @@
+use super::program_runtime::{
+    can_stop_matching, rose_deliver_reports, rose_flush_combinations, rose_flush_reports,
+    MatchQueue,
+};
+use crate::nfa::{nfa_exec, nfa_exec_eod, StateSet};
+use crate::rose::RoseEngine;
+use crate::runtime::Matching;
+use crate::scratch::Scratch;
+use bit_vec::BitVec;
+
+/// Exhaustion keys of the single-match patterns which have matched.
+#[derive(Clone, Default)]
+pub(crate) struct ExhaustedKeys {
+    keys: BitVec,
+    /// Number of keys set, so that exhaustion of them all is cheap to check.
+    count: u32,
+}
+
+impl ExhaustedKeys {
+    fn new(num_ekeys: u32) -> Self {
+        Self {
+            keys: BitVec::from_elem(num_ekeys as usize, false),
+            count: 0,
+        }
+    }
+
+    pub(crate) fn get(&self, ekey: u32) -> bool {
+        self.keys[ekey as usize]
+    }
+
+    /// Sets `ekey`, which must not be set yet.
+    pub(crate) fn set(&mut self, ekey: u32) {
+        debug_assert!(!self.get(ekey));
+        self.keys.set(ekey as usize, true);
+        self.count += 1;
+    }
+
+    pub(crate) fn count(&self) -> u32 {
+        self.count
+    }
+}
+
+/// State carried over from one write of a stream to the next.
+#[derive(Clone, Default)]
+pub(crate) struct RoseState {
+    /// NFA states on after the last write.
+    pub(crate) nfa: StateSet,
+    /// Exhaustion keys of the single-match patterns which have matched.
+    pub(crate) exhausted: ExhaustedKeys,
+    /// Logical keys of the sub-expressions of combinations matched so far.
+    pub(crate) logical: BitVec,
+    /// Matches raised but not delivered yet.
+    pub(crate) queue: MatchQueue,
+}
+
+impl RoseState {
+    /// Creates the initial state of a stream scanned with `rose`.
+    pub(crate) fn new(rose: &RoseEngine) -> Self {
+        Self {
+            nfa: StateSet::new(rose.nfa.num_states()),
+            exhausted: ExhaustedKeys::new(rose.num_ekeys),
+            logical: BitVec::from_elem(rose.num_lkeys as usize, false),
+            queue: MatchQueue::default(),
+        }
+    }
+
+    /// Makes sure the state can be used to scan with `rose`.
+    pub(crate) fn reserve(&mut self, rose: &RoseEngine) {
+        self.nfa.reserve(rose.nfa.num_states());
+        for (keys, num_keys) in &mut [
+            (&mut self.exhausted.keys, rose.num_ekeys),
+            (&mut self.logical, rose.num_lkeys),
+        ] {
+            let num_keys = *num_keys as usize;
+            if keys.len() < num_keys {
+                keys.grow(num_keys - keys.len(), false);
+            }
+        }
+    }
+
+    /// Resets the state to that of a stream nothing was written to.
+    pub(crate) fn clear(&mut self) {
+        self.nfa.clear();
+        self.exhausted.keys.clear();
+        self.exhausted.count = 0;
+        self.logical.clear();
+        self.queue.clear();
+    }
+
+    /// Returns the number of bytes allocated on the heap for the state.
+    pub(crate) fn heap_size(&self) -> usize {
+        self.nfa.heap_size()
+            + (self.exhausted.keys.capacity() + self.logical.capacity()) / 8
+            + self.queue.heap_size()
+    }
+}
+
+/// Scans `data`, whose first byte is at stream offset `offset`, carrying the
+/// state in `state` over from the previous write.
+///
+/// Scanning stops early, without error, once every pattern is exhausted.
+pub(crate) fn rose_stream_exec<F>(
+    rose: &RoseEngine,
+    state: &mut RoseState,
+    scratch: &mut Scratch,
+    data: &[u8],
+    offset: u64,
+    on_match: &mut F,
+) -> Matching
+where
+    F: FnMut(u32, u64, u64) -> Matching,
+{
+    state.reserve(rose);
+    scratch.next.reserve(rose.nfa.num_states());
+
+    let RoseState {
+        nfa,
+        exhausted,
+        logical,
+        queue,
+    } = state;
+    if can_stop_matching(rose, exhausted) {
+        return Matching::Continue;
+    }
+
+    // Vacuous patterns match at offset 0 once we know the data isn't empty.
+    if offset == 0 && !data.is_empty() {
+        let status = rose_deliver_reports(
+            rose,
+            exhausted,
+            logical,
+            queue,
+            &rose.boundary.report_zero_offset,
+            0,
+            on_match,
+        );
+        if let Matching::Terminate = status {
+            return status;
+        }
+        if can_stop_matching(rose, exhausted) {
+            return Matching::Continue;
+        }
+    }
+
+    let mut status = Matching::Continue;
+    nfa_exec(
+        &rose.nfa,
+        nfa,
+        &mut scratch.next,
+        &mut scratch.reports,
+        data,
+        offset,
+        |r, end| {
+            status = rose_deliver_reports(rose, exhausted, logical, queue, r, end, on_match);
+            if can_stop_matching(rose, exhausted) {
+                return Matching::Terminate;
+            }
+            status
+        },
+    );
+    status
+}
+
+/// Raises the matches due at the end of data, which is at stream offset
+/// `offset`, including those of vacuous patterns and of the logical
+/// combinations holding.
+pub(crate) fn rose_stream_eod_exec<F>(
+    rose: &RoseEngine,
+    state: &mut RoseState,
+    scratch: &mut Scratch,
+    offset: u64,
+    on_match: &mut F,
+) -> Matching
+where
+    F: FnMut(u32, u64, u64) -> Matching,
+{
+    state.reserve(rose);
+
+    let RoseState {
+        nfa,
+        exhausted,
+        logical,
+        queue,
+    } = state;
+    if can_stop_matching(rose, exhausted) {
+        return Matching::Continue;
+    }
+
+    let status = nfa_exec_eod(&rose.nfa, nfa, &mut scratch.reports, offset, |r, end| {
+        rose_deliver_reports(rose, exhausted, logical, queue, r, end, on_match)
+    });
+    if let Matching::Terminate = status {
+        return status;
+    }
+
+    // Empty data gets the reports of offset 0 as well as those of end of
+    // data.
+    let boundary = if offset == 0 {
+        &rose.boundary.report_zero_eod_offset
+    } else {
+        &rose.boundary.report_eod_offset
+    };
+    let status = rose_deliver_reports(rose, exhausted, logical, queue, boundary, offset, on_match);
+    if let Matching::Terminate = status {
+        return status;
+    }
+    let status = rose_flush_reports(rose, exhausted, logical, queue, on_match);
+    if let Matching::Terminate = status {
+        return status;
+    }
+    rose_flush_combinations(rose, logical, &mut scratch.reports, offset, on_match)
+}
@@ -1,7 +1,13 @@
 use crate::database::{get_bytecode, Database};
-use crate::rose::{rose_block_exec, RoseEngine, RoseRuntimeImpl};
+use crate::rose::{
+    rose_block_exec, rose_stream_eod_exec, rose_stream_exec, rose_vectored_exec, RoseEngine,
+    RoseRuntimeImpl, RoseState,
+};
 use crate::scratch::Scratch;
+use crate::Mode;
+use std::mem;
 
+#[derive(Debug, PartialEq)]
 pub enum Error {
     /// A parameter passed to this function was invalid.
     Invalid,
@@ -9,34 +15,798 @@ pub enum Error {
     ScanTerminated,
     /// Unexpected internal error.
     Unknown,
+    /// The serialized database was built by an incompatible version of this
+    /// library.
+    DbVersion,
+    /// The serialized database is corrupt.
+    DbCorrupt,
+}
+
+/// Return value of a match callback, telling the engine whether to go on
+/// scanning.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Matching {
+    /// Continue scanning.
+    Continue,
+    /// Stop scanning; the scan returns [`Error::ScanTerminated`].
+    Terminate,
+}
+
+/// A match reported by the engine.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Match {
+    /// ID of the matching pattern.
+    pub id: u32,
+    /// Start offset of the match; zero unless start-of-match reporting was
+    /// requested for the pattern.
+    pub from: u64,
+    /// End offset of the match.
+    pub to: u64,
 }
 
 /// Initializes SOM state. Used in both block and streaming mode.
-fn init_som_state(_rose: &RoseEngine, _state: *mut u8) {}
+fn init_som_state(_rose: &RoseEngine, _scratch: &mut Scratch) {}
 
-fn raw_block_exec(rose: &RoseEngine, scratch: &mut Scratch) {
-    init_som_state(rose, scratch.core_info.state);
+/// Runs a database in block mode.
+///
+/// Literal-only and single outfix databases are matched by the same
+/// automaton as everything else for now.
+fn raw_block_exec<F>(
+    rose: &RoseEngine,
+    data: &[u8],
+    scratch: &mut Scratch,
+    on_match: &mut F,
+) -> Matching
+where
+    F: FnMut(u32, u64, u64) -> Matching,
+{
+    init_som_state(rose, scratch);
 
-    rose_block_exec(rose);
+    rose_block_exec(rose, scratch, data, on_match)
 }
 
-fn pure_literal_block_exec(_rose: &RoseEngine, _scratch: &mut Scratch) {}
+/// Performs pattern matching against the pattern database.
+///
+/// `on_match` is called for every match with the ID of the matching pattern
+/// and the start and end offsets of the match. The start offset is only
+/// accurate for patterns compiled with start-of-match reporting; it is zero
+/// otherwise.
+///
+/// # Errors
+///
+/// Returns [`Error::Invalid`] if `db` was not compiled for block mode, or
+/// [`Error::ScanTerminated`] if `on_match` returned [`Matching::Terminate`].
+pub fn scan<F>(
+    db: &Database,
+    data: &[u8],
+    scratch: &mut Scratch,
+    mut on_match: F,
+) -> Result<(), Error>
+where
+    F: FnMut(u32, u64, u64) -> Matching,
+{
+    let rose = get_bytecode(db);
+    if !matches!(rose.mode, Mode::Block) {
+        return Err(Error::Invalid);
+    }
 
-fn single_outfix_block_exec(_rose: &RoseEngine, _scratch: &mut Scratch) {}
+    let status = match rose.runtime_impl {
+        RoseRuntimeImpl::FullRose
+        | RoseRuntimeImpl::PureLiteral
+        | RoseRuntimeImpl::SingleOutfix => raw_block_exec(rose, data, scratch, &mut on_match),
+    };
 
-/// Performs pattern matching against the pattern database.
+    match status {
+        Matching::Continue => Ok(()),
+        Matching::Terminate => Err(Error::ScanTerminated),
+    }
+}
+
+/// Performs pattern matching against the pattern database, treating the
+/// buffers in `data` as a single contiguous block.
+///
+/// `on_match` is called as in [`scan`], with offsets relative to the start of
+/// the first buffer; matches may span buffer boundaries.
 ///
 /// # Errors
 ///
-/// Currently this function never returns an error.
-pub fn scan(db: &Database, _data: &[u8], scratch: &mut Scratch) -> Result<(), Error> {
+/// Returns [`Error::Invalid`] if `db` was not compiled for vectored mode, or
+/// [`Error::ScanTerminated`] if `on_match` returned [`Matching::Terminate`].
+pub fn scan_vectored<F>(
+    db: &Database,
+    data: &[&[u8]],
+    scratch: &mut Scratch,
+    mut on_match: F,
+) -> Result<(), Error>
+where
+    F: FnMut(u32, u64, u64) -> Matching,
+{
     let rose = get_bytecode(db);
+    if !matches!(rose.mode, Mode::Vectored) {
+        return Err(Error::Invalid);
+    }
+
+    match rose_vectored_exec(rose, scratch, data, &mut on_match) {
+        Matching::Continue => Ok(()),
+        Matching::Terminate => Err(Error::ScanTerminated),
+    }
+}
+
+/// The state of a stream being scanned.
+///
+/// A stream is opened with [`Database::open_stream`], fed with
+/// [`Stream::scan`] and closed with [`close_stream`]. Matches may span any
+/// number of writes.
+pub struct Stream<'d> {
+    db: &'d Database,
+
+    /// State carried over from the last write.
+    state: RoseState,
+
+    /// Number of bytes scanned so far.
+    offset: u64,
+
+    /// Set once a callback has asked to stop matching.
+    terminated: bool,
+}
+
+impl<'d> Stream<'d> {
+    pub(crate) fn new(db: &'d Database) -> Self {
+        Self {
+            db,
+            state: RoseState::new(get_bytecode(db)),
+            offset: 0,
+            terminated: false,
+        }
+    }
+
+    /// Returns the size of the stream state in bytes.
+    #[must_use]
+    pub fn size(&self) -> usize {
+        mem::size_of::<Self>() + self.state.heap_size()
+    }
+
+    /// Scans the next chunk of data written to the stream.
+    ///
+    /// `on_match` is called as in [`scan`], with offsets relative to the
+    /// start of the stream.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ScanTerminated`] if `on_match` returned
+    /// [`Matching::Terminate`], now or on an earlier write; no more matches
+    /// are reported from the stream afterwards.
+    pub fn scan<F>(
+        &mut self,
+        data: &[u8],
+        scratch: &mut Scratch,
+        mut on_match: F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(u32, u64, u64) -> Matching,
+    {
+        if self.terminated {
+            return Err(Error::ScanTerminated);
+        }
+
+        let rose = get_bytecode(self.db);
+        let status = rose_stream_exec(
+            rose,
+            &mut self.state,
+            scratch,
+            data,
+            self.offset,
+            &mut on_match,
+        );
+        self.offset += data.len() as u64;
+
+        match status {
+            Matching::Continue => Ok(()),
+            Matching::Terminate => {
+                self.terminated = true;
+                Err(Error::ScanTerminated)
+            }
+        }
+    }
+}
+
+/// Closes a stream, reporting the matches that can only be raised at the end
+/// of data, including those of logical combinations.
+///
+/// # Errors
+///
+/// Returns [`Error::ScanTerminated`] if `on_match` returned
+/// [`Matching::Terminate`], or if the stream had already been terminated.
+pub fn close_stream<F>(
+    mut stream: Stream,
+    scratch: &mut Scratch,
+    mut on_match: F,
+) -> Result<(), Error>
+where
+    F: FnMut(u32, u64, u64) -> Matching,
+{
+    if stream.terminated {
+        return Err(Error::ScanTerminated);
+    }
+
+    let rose = get_bytecode(stream.db);
+    match rose_stream_eod_exec(
+        rose,
+        &mut stream.state,
+        scratch,
+        stream.offset,
+        &mut on_match,
+    ) {
+        Matching::Continue => Ok(()),
+        Matching::Terminate => Err(Error::ScanTerminated),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{compile, compile_multi, Flags, Mode};
+
+    fn matches(db: &Database, data: &[u8]) -> Vec<(u32, u64, u64)> {
+        let mut scratch = Scratch::new(db);
+        let mut out = Vec::new();
+        scan(db, data, &mut scratch, |id, from, to| {
+            out.push((id, from, to));
+            Matching::Continue
+        })
+        .expect("scan");
+        out
+    }
+
+    /// Returns the end offsets of the matches of `pattern` in `data`.
+    fn ends(pattern: &str, flags: Flags, data: &[u8]) -> Vec<u64> {
+        let db = compile(pattern, flags, &Mode::Block).expect("valid");
+        matches(&db, data).into_iter().map(|m| m.2).collect()
+    }
+
+    #[test]
+    fn scan_literal() {
+        let db = compile("foo", Flags::empty(), &Mode::Block).expect("valid");
+        assert_eq!(matches(&db, b"xfoofoo"), vec![(0, 0, 4), (0, 0, 7)]);
+        assert!(matches(&db, b"fo").is_empty());
+    }
+
+    #[test]
+    fn scan_caseless_literal() {
+        let db = compile("foo", Flags::CASELESS, &Mode::Block).expect("valid");
+        assert_eq!(matches(&db, b"FoO"), vec![(0, 0, 3)]);
+    }
+
+    #[test]
+    fn scan_alternation() {
+        let db = compile("a(b|cd)e", Flags::empty(), &Mode::Block).expect("valid");
+        assert_eq!(matches(&db, b"abe ace acde"), vec![(0, 0, 3), (0, 0, 12)]);
+    }
+
+    #[test]
+    fn scan_repeats() {
+        let none = Flags::empty();
+        assert_eq!(ends("ab*c", none, b"ac abc abbbc"), vec![2, 6, 12]);
+        assert_eq!(ends("ab+c", none, b"ac abc abbc"), vec![6, 11]);
+        assert_eq!(ends("ab?c", none, b"ac abc abbc"), vec![2, 6]);
+        assert_eq!(ends("a{2}", none, b"aaa"), vec![2, 3]);
+        assert_eq!(ends("xa{2,3}", none, b"xaaaa"), vec![3, 4]);
+        assert_eq!(ends("xa{2,}", none, b"xaaaa"), vec![3, 4, 5]);
+        assert_eq!(ends("xa+?", none, b"xaa"), vec![2, 3]);
+        assert_eq!(ends("x(ab|c)*y", none, b"xy xababcy xaby"), vec![2, 10, 15]);
+        assert_eq!(ends("(a?){2}b", none, b"b ab aab aaab"), vec![1, 4, 8, 13]);
+        assert_eq!(ends("x(a|b?){2,}y", none, b"xy xaby"), vec![2, 7]);
+        assert_eq!(ends("xa{0}y", none, b"xy xay"), vec![2]);
+        assert_eq!(ends("a{,2}", none, b"a{,2}"), vec![5]);
+    }
+
+    #[test]
+    fn scan_classes() {
+        let none = Flags::empty();
+        assert_eq!(ends("x[a-c_]", none, b"xa xd x_ xc"), vec![2, 8, 11]);
+        assert_eq!(ends("x[^a-c]", none, b"xa xd xc"), vec![5]);
+        assert_eq!(ends("x[]a]", none, b"x] xa xb"), vec![2, 5]);
+        assert_eq!(ends("x[a-]", none, b"x- xa xb"), vec![2, 5]);
+        assert_eq!(ends("x[-a]", none, b"x- xa xb"), vec![2, 5]);
+        assert_eq!(ends("x[\\]\\-]", none, b"x] x- xa"), vec![2, 5]);
+        assert_eq!(ends("x[a-c-e]", none, b"xb x- xd xe"), vec![2, 5, 11]);
+        assert_eq!(ends("x[[:digit:]_]", none, b"x1 x_ xa"), vec![2, 5]);
+        assert_eq!(ends("x[[:^alpha:]]", none, b"x1 xa"), vec![2]);
+        assert_eq!(ends("x[b-c]", Flags::CASELESS, b"xB xa"), vec![2]);
+        assert_eq!(ends("x[[:upper:]]", Flags::CASELESS, b"xb x1"), vec![2]);
+    }
+
+    #[test]
+    fn scan_escapes() {
+        let none = Flags::empty();
+        assert_eq!(ends("foo\\.bar", none, b"fooxbar foo.bar"), vec![15]);
+        assert_eq!(ends("x\\d\\D", none, b"x1a x12 xab"), vec![3]);
+        assert_eq!(ends("x\\w\\W", none, b"x_. x_a"), vec![3]);
+        assert_eq!(ends("x\\s\\S", none, b"x\ta x\t\t"), vec![3]);
+        assert_eq!(
+            ends("\\n\\r\\t\\f\\e\\a", none, b"\n\r\t\x0c\x1b\x07"),
+            vec![6]
+        );
+        assert_eq!(ends("x\\v", none, b"x\x0b x\n xv"), vec![2, 5]);
+        assert_eq!(ends("\\x41\\x{42}\\x7", none, b"AB\x07"), vec![3]);
+        assert_eq!(ends("\\o{101}\\012\\0", none, b"A\n\x00"), vec![3]);
+        assert_eq!(ends("\\cA\\c[\\cz", none, b"\x01\x1b\x1a"), vec![3]);
+        assert_eq!(ends("\\(\\*\\\\", none, b"(*\\"), vec![3]);
+        assert_eq!(
+            ends("x[\\d\\x41-\\x43\\b]", none, b"x5 xB x\x08 xD"),
+            vec![2, 5, 8]
+        );
+        assert_eq!(ends("x[\\W]", none, b"x. xa"), vec![2]);
+    }
+
+    #[test]
+    fn scan_dot() {
+        let none = Flags::empty();
+        let data = "abc a\nc a\u{e9}c a\u{1f600}c".as_bytes();
+        assert_eq!(ends("a.c", none, data), vec![3]);
+        assert_eq!(ends("a.c", Flags::DOTALL, data), vec![3, 7]);
+        assert_eq!(ends("a..c", none, data), vec![12]);
+        assert_eq!(ends("a.c", Flags::UTF8, data), vec![3, 12, 19]);
+        assert_eq!(
+            ends("a.c", Flags::UTF8 | Flags::DOTALL, data),
+            vec![3, 7, 12, 19]
+        );
+        assert_eq!(ends("a[^b]c", Flags::UTF8, data), vec![7, 12, 19]);
+    }
+
+    #[test]
+    fn scan_anchors() {
+        let none = Flags::empty();
+        let m = Flags::MULTILINE;
+        assert_eq!(ends("^ab", none, b"abab"), vec![2]);
+        assert!(ends("^ab", none, b"xab").is_empty());
+        assert_eq!(ends("\\Aab", m, b"ab\nab"), vec![2]);
+        assert_eq!(ends("ab$", none, b"abab"), vec![4]);
+        assert_eq!(ends("ab$", none, b"abab\n"), vec![4]);
+        assert!(ends("ab$", none, b"ab\n\n").is_empty());
+        assert_eq!(ends("ab\\Z", none, b"ab\n"), vec![2]);
+        assert!(ends("ab\\z", none, b"ab\n").is_empty());
+        assert_eq!(ends("ab\\z", none, b"abab"), vec![4]);
+        assert_eq!(ends("^ab$", none, b"ab"), vec![2]);
+        assert_eq!(ends("(^a|b)c", none, b"acbcac"), vec![2, 4]);
+        assert_eq!(ends("a(b|$)", none, b"abxa"), vec![2, 4]);
+        assert_eq!(ends("^ab", m, b"ab\nab xab\n"), vec![2, 5]);
+        assert_eq!(ends("ab$", m, b"ab\nabx\nab"), vec![2, 9]);
+        assert_eq!(ends("^$", m, b"a\n\nb\n\n"), vec![2, 5]);
+        assert_eq!(ends("^x$", m, b"x\nx\n"), vec![1, 3]);
+    }
+
+    #[test]
+    fn scan_word_boundaries() {
+        let none = Flags::empty();
+        let data = b"cat concat cats cat_ (cat)";
+        assert_eq!(ends("\\bcat\\b", none, data), vec![3, 25]);
+        assert_eq!(ends("\\bcat", none, data), vec![3, 14, 19, 25]);
+        assert_eq!(ends("cat\\b", none, data), vec![3, 10, 25]);
+        assert_eq!(ends("\\Bcat", none, data), vec![10]);
+        assert_eq!(ends("cat\\B", none, data), vec![14, 19]);
+        assert_eq!(ends("a\\b.", none, b"ab a. a"), vec![5]);
+        assert_eq!(ends("\\b[a-z]+\\b", none, b"ab c"), vec![2, 4]);
+        assert_eq!(ends("x\\b$", none, b"x\n"), vec![1]);
+
+        // Patterns made of nothing but an assertion.
+        assert_eq!(ends("\\b", none, b"ab cd"), vec![0, 2, 3, 5]);
+        assert_eq!(ends("\\B", none, b"ab"), vec![1]);
+        assert_eq!(ends("\\B", none, b""), vec![0]);
+        assert!(ends("\\b", none, b"").is_empty());
+        assert_eq!(ends("x?\\b", none, b"a x"), vec![0, 1, 2, 3]);
+        assert_eq!(ends("(?:\\b|-)", none, b"a-"), vec![0, 1, 2]);
+        assert_eq!(ends("(?:\\b|q)", none, b"q -"), vec![0, 1]);
+
+        // Optional assertions may be skipped.
+        assert_eq!(ends("a(?:\\b)?", none, b"ab"), vec![1]);
+        assert_eq!(ends("(?:\\b)?\\z", Flags::ALLOWEMPTY, b"a "), vec![2]);
+        assert_eq!(ends("a(?:\\B){0,2}b", none, b"ab a b"), vec![2]);
+        assert_eq!(ends("(?:\\b)*-", none, b"a--"), vec![2, 3]);
+
+        // Chained assertions must all hold.
+        assert!(ends("a\\B\\b", none, b"a").is_empty());
+        assert!(ends("a\\b\\B", none, b"a ").is_empty());
+        assert!(ends("\\B\\b", Flags::ALLOWEMPTY, b"a b").is_empty());
+        assert_eq!(ends("a\\b\\b", none, b"ab a"), vec![4]);
+        assert_eq!(ends("a\\b(?:\\B|\\b)", none, b"ab a"), vec![4]);
+        assert_eq!(ends("a(?:\\b|\\B)", none, b"ab a"), vec![1, 4]);
+
+        // Matches found by looking at the next byte come in order, once.
+        assert_eq!(ends("(?:a\\b|a)", none, b"a b"), vec![1]);
+        assert_eq!(ends("(?:a\\b|a )", none, b"a "), vec![1, 2]);
+        assert_eq!(ends("(?:a\\b|a)", none, b"a"), vec![1]);
+
+        let data = "caf\u{e9} cafe".as_bytes();
+        assert_eq!(ends("caf\\b", none, data), vec![3]);
+        assert!(ends("caf\\b", Flags::UTF8 | Flags::UCP, data).is_empty());
+
+        // In UCP mode, characters are word characters by code point.
+        let ucp = Flags::UTF8 | Flags::UCP;
+        assert_eq!(ends("\\bfoo\\b", ucp, "foo\u{2014}bar".as_bytes()), vec![3]);
+        assert_eq!(ends("\\bfoo", ucp, "\u{ab}foo".as_bytes()), vec![5]);
+        assert_eq!(ends("foo\\b", ucp, "foo\u{e9} foo".as_bytes()), vec![9]);
+        assert_eq!(ends("x\\B", ucp, "x\u{e9}".as_bytes()), vec![1]);
+        assert_eq!(ends("\\b\u{e9}", ucp, "\u{e9} a\u{e9}".as_bytes()), vec![2]);
+        assert_eq!(
+            ends("\u{e9}\\b", ucp, "\u{e9}a \u{e9}\u{2014}".as_bytes()),
+            vec![6]
+        );
+        assert_eq!(ends("\\b", ucp, "\u{e9}\u{2014}".as_bytes()), vec![0, 2]);
+        assert_eq!(ends("\\B", ucp, "\u{2014}\u{ab}".as_bytes()), vec![0, 3, 5]);
+    }
+
+    #[test]
+    fn scan_option_groups() {
+        let none = Flags::empty();
+        assert_eq!(ends("x(?:ab)+y", none, b"xy xaby xababy"), vec![7, 14]);
+        assert_eq!(ends("a(?i)b", none, b"ab aB AB"), vec![2, 5]);
+        assert_eq!(ends("a(?i:b)c", none, b"aBc aBC"), vec![3]);
+        assert_eq!(ends("(?i)a(?-i)b", none, b"Ab AB"), vec![2]);
+        assert_eq!(ends("(a(?i)b|c)d", none, b"aBd Cd aBD"), vec![3, 6]);
+        assert_eq!(ends("(?s)a.c", none, b"a\nc"), vec![3]);
+        assert_eq!(ends("(?s-s:a.c)", none, b"a\nc"), Vec::<u64>::new());
+        assert_eq!(ends("(?m)^b", none, b"a\nb"), vec![3]);
+    }
+
+    #[test]
+    fn scan_extended() {
+        let none = Flags::empty();
+        assert_eq!(
+            ends("(?x) a b # comment\n c +", none, b"abc abcc"),
+            vec![3, 7, 8]
+        );
+        assert_eq!(ends("(?x)a\\ b[ ]c", none, b"a b c abc"), vec![5]);
+        assert_eq!(ends("(?x: a b )c d", none, b"abc d abcd"), vec![5]);
+        assert_eq!(ends("a(?#comment)b", none, b"ab"), vec![2]);
+        assert_eq!(ends("(?x)a(?-x) b", none, b"ab a b"), vec![6]);
+    }
+
+    #[test]
+    fn scan_quoted() {
+        let none = Flags::empty();
+        assert_eq!(ends("\\Qa.b*\\E+", none, b"axb a.b**"), vec![8, 9]);
+        assert_eq!(ends("x\\Q(y|z)", none, b"xy x(y|z)"), vec![9]);
+        assert_eq!(ends("[\\Q]-\\E]+", none, b"a]-b"), vec![2, 3]);
+        assert_eq!(ends("[\\Qa\\E-c]", none, b"b-"), vec![1]);
+        assert_eq!(ends("a\\Q\\E+", none, b"aa"), vec![1, 2]);
+        assert_eq!(ends("a\\Eb", none, b"ab"), vec![2]);
+        assert_eq!(ends("(?x)\\Q a #\\E", none, b"a # a #"), vec![7]);
+    }
+
+    #[test]
+    fn scan_prefilter() {
+        assert_eq!(ends("(a)\\1b", Flags::PREFILTER, b"aab ab"), vec![3, 6]);
+        assert_eq!(ends("a(?=b)", Flags::PREFILTER, b"ab ac"), vec![1, 4]);
+        assert_eq!(
+            ends("(?(1)b|c)d", Flags::PREFILTER, b"bd cd ed"),
+            vec![2, 5]
+        );
+        assert_eq!(ends("x{2,200}", Flags::PREFILTER, b"xxx"), vec![2, 3]);
+
+        let e = compile("(a)\\1b", Flags::empty(), &Mode::Block)
+            .err()
+            .expect("unsupported");
+        assert_eq!(e.reason, "Backreferences are not supported at index 3");
+    }
+
+    #[test]
+    fn scan_utf8_literals() {
+        let data = "caf\u{e9} cafe \u{1f600}\u{1f600}".as_bytes();
+        assert_eq!(ends("caf\u{e9}", Flags::UTF8, data), vec![5]);
+        assert_eq!(ends("caf\\x{e9}", Flags::UTF8, data), vec![5]);
+        assert_eq!(ends("\u{1f600}+", Flags::UTF8, data), vec![15, 19]);
+        assert_eq!(ends("caf[\u{e9}e]", Flags::UTF8, data), vec![5, 10]);
+        assert_eq!(ends("caf\\\u{e9}", Flags::UTF8, data), vec![5]);
+        assert_eq!(ends("caf[\\\u{e9}]", Flags::UTF8, data), vec![5]);
+        assert_eq!(ends("caf\\xe9", Flags::empty(), b"caf\xe9"), vec![4]);
+    }
+
+    #[test]
+    fn scan_caseless_utf8() {
+        let nocase = Flags::UTF8 | Flags::CASELESS;
+        assert_eq!(
+            ends("caf\u{e9}", nocase, "CAF\u{c9} caf\u{e9}".as_bytes()),
+            vec![5, 11]
+        );
+        assert_eq!(
+            ends("\u{3c3}", nocase, "\u{3a3}\u{3c2}\u{3c3}".as_bytes()),
+            vec![2, 4, 6]
+        );
+        assert_eq!(ends("ok", nocase, "OK o\u{212a}".as_bytes()), vec![2, 7]);
+        assert_eq!(
+            ends("[\u{e0}-\u{e5}]", nocase, "\u{c4}".as_bytes()),
+            vec![2]
+        );
+        assert_eq!(
+            ends("\u{e9}", Flags::UTF8, "\u{c9}".as_bytes()),
+            Vec::<u64>::new()
+        );
+
+        // Without UTF-8, only ASCII letters are folded.
+        assert_eq!(
+            ends("ok", Flags::CASELESS, "OK o\u{212a}".as_bytes()),
+            vec![2]
+        );
+    }
+
+    #[test]
+    fn scan_unicode_properties() {
+        let utf8 = Flags::UTF8;
+        let data = "a \u{3b1}\u{3b2} \u{416} 7\u{663}".as_bytes();
+        assert_eq!(ends("\\p{Greek}+", utf8, data), vec![4, 6]);
+        assert_eq!(ends("\\p{Cyrillic}", utf8, data), vec![9]);
+        assert_eq!(ends("\\pL", utf8, data), vec![1, 4, 6, 9]);
+        assert_eq!(ends("\\p{Lu}", utf8, data), vec![9]);
+        assert_eq!(ends("\\p{L&}", utf8, data), vec![1, 4, 6, 9]);
+        assert_eq!(ends("\\p{^L}\\p{Nd}", utf8, data), vec![11, 13]);
+        assert_eq!(ends("\\P{L}\\p{Nd}", utf8, data), vec![11, 13]);
+        assert_eq!(ends("[\\p{Greek}\\p{Nd}]{2}", utf8, data), vec![6, 13]);
+
+        // Shorthand classes only cover Unicode under UCP.
+        assert_eq!(ends("\\w\\w", utf8, data), Vec::<u64>::new());
+        assert_eq!(ends("\\w\\w", utf8 | Flags::UCP, data), vec![6, 13]);
+        assert_eq!(ends("\\d+", utf8 | Flags::UCP, data), vec![11, 13]);
+        assert_eq!(ends("[[:upper:]]", utf8 | Flags::UCP, data), vec![9]);
+
+        // Caseless, the cased letter categories match letters of any case.
+        let caseless = utf8 | Flags::CASELESS;
+        assert_eq!(ends("\\p{Lu}", caseless, data), vec![1, 4, 6, 9]);
+        assert_eq!(ends("[\\p{Ll}]", caseless, data), vec![1, 4, 6, 9]);
+        assert_eq!(ends("(?i)\\p{Lt}", utf8, "a\u{1c5}".as_bytes()), vec![1, 3]);
+        assert_eq!(
+            ends("[[:upper:]]", caseless | Flags::UCP, data),
+            ends("\\p{Lu}", caseless, data)
+        );
+        assert_eq!(
+            ends("\\s", utf8 | Flags::UCP, "a\u{3000}".as_bytes()),
+            vec![4]
+        );
 
-    match rose.runtime_impl {
-        RoseRuntimeImpl::FullRose => raw_block_exec(rose, scratch),
-        RoseRuntimeImpl::PureLiteral => pure_literal_block_exec(rose, scratch),
-        RoseRuntimeImpl::SingleOutfix => single_outfix_block_exec(rose, scratch),
+        // Without UTF-8, properties match the bytes of Latin-1 characters.
+        assert_eq!(ends("\\p{Ll}", Flags::empty(), b"A\xe9"), vec![2]);
     }
 
-    Ok(())
+    #[test]
+    fn scan_multi() {
+        let db = compile_multi(
+            &["abc", "b(c|d)"],
+            &[Flags::empty(), Flags::empty()],
+            &[1, 2],
+            &Mode::Block,
+        )
+        .expect("valid");
+        assert_eq!(
+            matches(&db, b"abcbd"),
+            vec![(1, 0, 3), (2, 0, 3), (2, 0, 5)]
+        );
+    }
+
+    fn stream_matches(db: &Database, chunks: &[&[u8]]) -> Vec<(u32, u64, u64)> {
+        let mut scratch = Scratch::new(db);
+        let mut out = Vec::new();
+        let mut stream = db.open_stream().expect("streaming database");
+        for chunk in chunks {
+            stream
+                .scan(chunk, &mut scratch, |id, from, to| {
+                    out.push((id, from, to));
+                    Matching::Continue
+                })
+                .expect("scan");
+        }
+        close_stream(stream, &mut scratch, |id, from, to| {
+            out.push((id, from, to));
+            Matching::Continue
+        })
+        .expect("close");
+        out
+    }
+
+    #[test]
+    fn scan_single_match() {
+        let db = compile_multi(
+            &["a", "b", "c"],
+            &[Flags::SINGLEMATCH, Flags::empty(), Flags::SINGLEMATCH],
+            &[1, 2, 1],
+            &Mode::Block,
+        )
+        .expect("valid");
+        assert!(!get_bytecode(&db).global_exhaust);
+        assert_eq!(
+            matches(&db, b"cabab"),
+            vec![(1, 0, 1), (2, 0, 3), (2, 0, 5)]
+        );
+
+        // Exhaustion only lasts for a scan, but across the writes of a stream.
+        let mut scratch = Scratch::new(&db);
+        for _ in 0..2 {
+            let mut out = Vec::new();
+            scan(&db, b"aa", &mut scratch, |id, _, to| {
+                out.push((id, to));
+                Matching::Continue
+            })
+            .expect("scan");
+            assert_eq!(out, vec![(1, 1)]);
+        }
+
+        let db = compile_multi(
+            &["a", "b+"],
+            &[Flags::SINGLEMATCH; 2],
+            &[1, 2],
+            &Mode::Stream(None),
+        )
+        .expect("valid");
+        assert!(get_bytecode(&db).global_exhaust);
+        assert_eq!(
+            stream_matches(&db, &[b"ab", b"ba", b"b"]),
+            vec![(1, 0, 1), (2, 0, 2)]
+        );
+        assert_eq!(
+            stream_matches(&db, &[b"bb", b"a"]),
+            vec![(2, 0, 1), (1, 0, 3)]
+        );
+
+        let e = compile("a", Flags::SINGLEMATCH | Flags::SOM_LEFTMOST, &Mode::Block)
+            .err()
+            .expect("invalid flags");
+        assert_eq!(
+            e.reason,
+            "SINGLEMATCH is not supported in combination with SOM_LEFTMOST."
+        );
+    }
+
+    #[test]
+    fn scan_combinations() {
+        let patterns = ["foo", "bar", "baz", "(101 & 102) | !103", "101&!102"];
+        let flags = [
+            Flags::QUIET,
+            Flags::empty(),
+            Flags::QUIET,
+            Flags::COMBINATION,
+            Flags::COMBINATION | Flags::SINGLEMATCH,
+        ];
+        let ids = [101, 102, 103, 1, 2];
+        let db = compile_multi(&patterns, &flags, &ids, &Mode::Block).expect("valid");
+        assert_eq!(matches(&db, b"foo bar"), vec![(102, 0, 7), (1, 0, 7)]);
+        assert_eq!(matches(&db, b"foo baz"), vec![(2, 0, 7)]);
+        assert_eq!(matches(&db, b"baz"), vec![]);
+        assert_eq!(matches(&db, b"foo"), vec![(1, 0, 3), (2, 0, 3)]);
+
+        // Matches are remembered across writes, and not across scans.
+        let db = compile_multi(&patterns, &flags, &ids, &Mode::Stream(None)).expect("valid");
+        assert_eq!(
+            stream_matches(&db, &[b"fo", b"o b", b"ar"]),
+            vec![(102, 0, 7), (1, 0, 7)]
+        );
+        assert_eq!(stream_matches(&db, &[b"baz"]), vec![]);
+
+        let loaded = Database::deserialize(&db.serialize()).expect("deserialize");
+        assert_eq!(stream_matches(&loaded, &[b"foo", b"baz"]), vec![(2, 0, 6)]);
+    }
+
+    #[test]
+    fn scan_vacuous() {
+        let db = compile("a*", Flags::ALLOWEMPTY, &Mode::Block).expect("valid");
+        assert_eq!(db.min_width(), 0);
+        assert_eq!(matches(&db, b""), vec![(0, 0, 0)]);
+        assert_eq!(matches(&db, b"xa"), vec![(0, 0, 0), (0, 0, 1), (0, 0, 2)]);
+
+        // Anchored at either end, or at both.
+        let db = compile_multi(
+            &["^", "$", "^$"],
+            &[Flags::ALLOWEMPTY; 3],
+            &[1, 2, 3],
+            &Mode::Block,
+        )
+        .expect("valid");
+        assert_eq!(matches(&db, b""), vec![(1, 0, 0), (2, 0, 0), (3, 0, 0)]);
+        assert_eq!(matches(&db, b"ab"), vec![(1, 0, 0), (2, 0, 2)]);
+
+        let db = compile_multi(
+            &["^", "$", "^$"],
+            &[Flags::ALLOWEMPTY; 3],
+            &[1, 2, 3],
+            &Mode::Stream(None),
+        )
+        .expect("valid");
+        assert_eq!(
+            stream_matches(&db, &[b"", b""]),
+            vec![(1, 0, 0), (2, 0, 0), (3, 0, 0)]
+        );
+        assert_eq!(
+            stream_matches(&db, &[b"", b"ab", b"c"]),
+            vec![(1, 0, 0), (2, 0, 3)]
+        );
+
+        // Boundary reports honour single-match.
+        let db = compile(
+            "()",
+            Flags::ALLOWEMPTY | Flags::SINGLEMATCH,
+            &Mode::Stream(None),
+        )
+        .expect("valid");
+        assert_eq!(stream_matches(&db, &[b"ab", b"c"]), vec![(0, 0, 0)]);
+    }
+
+    #[test]
+    fn scan_stream_across_writes() {
+        let db = compile_multi(
+            &["foobar", "o(b|c)a"],
+            &[Flags::empty(), Flags::empty()],
+            &[1, 2],
+            &Mode::Stream(None),
+        )
+        .expect("valid");
+        assert_eq!(
+            stream_matches(&db, &[b"xxfo", b"o", b"bar"]),
+            vec![(2, 0, 7), (1, 0, 8)]
+        );
+
+        // A match seen from the first byte of the next write.
+        let db = compile("(?:a\\b|a)", Flags::empty(), &Mode::Stream(None)).expect("valid");
+        assert_eq!(
+            stream_matches(&db, &[b"a", b" a"]),
+            vec![(0, 0, 1), (0, 0, 3)]
+        );
+    }
+
+    #[test]
+    fn scan_vectored_across_buffers() {
+        let db = compile("foobar", Flags::empty(), &Mode::Vectored).expect("valid");
+        let mut scratch = Scratch::new(&db);
+        let mut out = Vec::new();
+        scan_vectored(
+            &db,
+            &[b"xfo", b"", b"ob", b"arfoobar"],
+            &mut scratch,
+            |id, from, to| {
+                out.push((id, from, to));
+                Matching::Continue
+            },
+        )
+        .expect("scan");
+        assert_eq!(out, vec![(0, 0, 7), (0, 0, 13)]);
+
+        let res = scan_vectored(&db, &[b"foo", b"bar"], &mut scratch, |_, _, _| {
+            Matching::Terminate
+        });
+        assert_eq!(res, Err(Error::ScanTerminated));
+    }
+
+    #[test]
+    fn scan_mode_mismatch() {
+        let db = compile("foo", Flags::empty(), &Mode::Stream(None)).expect("valid");
+        let mut scratch = Scratch::new(&db);
+        let res = scan(&db, b"foo", &mut scratch, |_, _, _| Matching::Continue);
+        assert_eq!(res, Err(Error::Invalid));
+
+        let db = compile("foo", Flags::empty(), &Mode::Block).expect("valid");
+        assert!(db.open_stream().is_err());
+        let res = scan_vectored(&db, &[b"foo"], &mut scratch, |_, _, _| Matching::Continue);
+        assert_eq!(res, Err(Error::Invalid));
+    }
+
+    #[test]
+    fn scan_stream_terminated() {
+        let db = compile("a", Flags::empty(), &Mode::Stream(None)).expect("valid");
+        let mut scratch = Scratch::new(&db);
+        let mut stream = db.open_stream().expect("streaming database");
+        let res = stream.scan(b"a", &mut scratch, |_, _, _| Matching::Terminate);
+        assert_eq!(res, Err(Error::ScanTerminated));
+        let res = stream.scan(b"a", &mut scratch, |_, _, _| Matching::Continue);
+        assert_eq!(res, Err(Error::ScanTerminated));
+    }
+
+    #[test]
+    fn scan_terminated() {
+        let db = compile("a", Flags::empty(), &Mode::Block).expect("valid");
+        let mut scratch = Scratch::new(&db);
+        let mut count = 0;
+        let res = scan(&db, b"aaa", &mut scratch, |_, _, _| {
+            count += 1;
+            Matching::Terminate
+        });
+        assert_eq!(res, Err(Error::ScanTerminated));
+        assert_eq!(count, 1);
+    }
 }
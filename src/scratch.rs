@@ -1,7 +1,98 @@
-pub(crate) struct CoreInfo {
-    pub(crate) state: *mut u8,
-}
+use crate::database::{get_bytecode, Database};
+use crate::nfa::StateSet;
+use crate::rose::RoseState;
+use crate::util::ReportId;
+use std::mem;
 
+/// Working memory used while scanning.
+///
+/// A scratch space is sized for the databases it is allocated or grown for.
+/// Scans take it by exclusive reference, so each thread scanning at the same
+/// time needs its own; [`Clone`] is the cheapest way to make one.
+#[derive(Clone)]
 pub struct Scratch {
-    pub(crate) core_info: CoreInfo,
+    /// State of the block being scanned, scanned as a stream made of a single
+    /// write.
+    pub(crate) state: RoseState,
+    /// NFA states on at the next offset.
+    pub(crate) next: StateSet,
+    /// Reports raised at the current offset.
+    pub(crate) reports: Vec<ReportId>,
+}
+
+impl Scratch {
+    /// Allocates a scratch space large enough to scan with `db`.
+    #[must_use]
+    pub fn new(db: &Database) -> Self {
+        let rose = get_bytecode(db);
+        Self {
+            state: RoseState::new(rose),
+            next: StateSet::new(rose.nfa.num_states()),
+            reports: Vec::new(),
+        }
+    }
+
+    /// Grows the scratch space, if needed, so that it can also be used to
+    /// scan with `db`.
+    ///
+    /// Scans grow the scratch space themselves when it is too small, but
+    /// doing it up front keeps allocation out of the scanning path.
+    pub fn grow(&mut self, db: &Database) {
+        let rose = get_bytecode(db);
+        self.state.reserve(rose);
+        self.next.reserve(rose.nfa.num_states());
+    }
+
+    /// Returns the size of the scratch space in bytes.
+    #[must_use]
+    pub fn size(&self) -> usize {
+        mem::size_of::<Self>()
+            + self.state.heap_size()
+            + self.next.heap_size()
+            + self.reports.capacity() * mem::size_of::<ReportId>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Scratch;
+    use crate::{compile, compile_multi, Flags, Mode};
+
+    #[test]
+    fn scratch_is_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Scratch>();
+    }
+
+    #[test]
+    fn grow() {
+        let small = compile("a", Flags::empty(), &Mode::Block).expect("valid");
+        let large = compile_multi(
+            &["foobar", "bazqux"],
+            &[Flags::empty(), Flags::empty()],
+            &[1, 2],
+            &Mode::Block,
+        )
+        .expect("valid");
+
+        let mut scratch = Scratch::new(&small);
+        let before = scratch.size();
+        scratch.grow(&large);
+        assert!(scratch.size() > before);
+        assert!(Scratch::new(&large).size() <= scratch.size());
+
+        let size = scratch.size();
+        scratch.grow(&small);
+        assert_eq!(scratch.size(), size);
+
+        let mut clone = scratch.clone();
+        assert!(clone.size() >= Scratch::new(&large).size());
+        let mut out = Vec::new();
+        crate::scan(&large, b"bazqux", &mut clone, |id, _, to| {
+            out.push((id, to));
+            crate::Matching::Continue
+        })
+        .expect("scan");
+        assert_eq!(out, vec![(2, 6)]);
+    }
 }
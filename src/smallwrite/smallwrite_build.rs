@@ -11,6 +11,7 @@ use std::ptr::NonNull;
 
 const MAX_TRIE_VERTICES: usize = 8_000;
 
+#[derive(Default)]
 struct LitTrieVertexProps {
     /// Character reached on this vertex.
     c: u8,
@@ -27,15 +28,6 @@ impl LitTrieVertexProps {
     }
 }
 
-impl Default for LitTrieVertexProps {
-    fn default() -> Self {
-        Self {
-            c: 0,
-            reports: HashSet::new(),
-        }
-    }
-}
-
 struct LitTrieEdgeProps;
 
 type LitTrieVertex = NodeIndex<DefaultIx>;
@@ -97,7 +89,7 @@ impl<'a> SmallWriteBuild<'a> {
         }
     }
 
-    #[allow(clippy::blocks_in_if_conditions)]
+    #[allow(clippy::blocks_in_conditions)]
     #[allow(dead_code)]
     pub(crate) fn add_expression(&mut self, g: &NgHolder, expr: &ExpressionInfo) {
         // If the graph is poisoned (i.e. we can't build a SmallWrite version),
@@ -7,19 +7,27 @@ pub(crate) mod compile_error;
 pub(crate) mod depth;
 #[cfg(test)]
 mod dump_charclass;
+mod logical;
 mod report;
 mod report_manager;
+#[rustfmt::skip]
+mod ucp_table;
+pub(crate) mod serialize;
 mod ue2common;
 mod ue2string;
 mod unicode_def;
+mod unicode_set;
 
 pub(crate) use boundary_reports::BoundaryReports;
-pub(crate) use charreach::{make_caseless, CharReach};
+pub(crate) use charreach::{fill_bitvector, from_bitvector, make_caseless, CharReach};
 pub(crate) use compile_context::CompileContext;
 pub(crate) use depth::Depth;
 #[cfg(test)]
 pub(crate) use dump_charclass::{describe_class, CcOutput};
-pub(crate) use report::{make_e_callback, make_som_relative_callback, Report};
+pub(crate) use logical::{CombInfo, LogicalOp};
+pub(crate) use report::{make_e_callback, make_som_relative_callback, Report, ReportType};
 pub(crate) use report_manager::{ExternalReportInfo, ReportManager};
 pub(crate) use ue2common::{ReportId, S64a};
 pub(crate) use ue2string::{mixed_sensitivity, Ue2Literal};
+pub(crate) use unicode_def::{ucp_property, ucp_word_chars, MAX_UNICODE};
+pub(crate) use unicode_set::{utf8_sequences, CodePointSet};
@@ -553,7 +553,7 @@ mod tests {
             a.set_range(i, i);
 
             let mut b = BitField256::default();
-            b.set(i.into());
+            b.set(i);
 
             assert_eq!(a, b);
         }
@@ -4,12 +4,18 @@ use std::collections::HashSet;
 #[derive(Default)]
 pub(crate) struct BoundaryReports {
     /// Set of internal reports to fire unconditionally at offset 0.
-    #[allow(dead_code)]
-    report_at_0: HashSet<ReportId>,
+    pub(crate) report_at_0: HashSet<ReportId>,
     /// Set of internal reports to fire unconditionally at offset 0 if it is eod.
-    #[allow(dead_code)]
-    report_at_0_eod: HashSet<ReportId>,
+    pub(crate) report_at_0_eod: HashSet<ReportId>,
     /// Set of internal reports to fire unconditionally at eod.
-    #[allow(dead_code)]
-    report_at_eod: HashSet<ReportId>,
+    pub(crate) report_at_eod: HashSet<ReportId>,
+}
+
+impl BoundaryReports {
+    /// Returns `true` if any report fires on a boundary.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.report_at_0.is_empty()
+            && self.report_at_0_eod.is_empty()
+            && self.report_at_eod.is_empty()
+    }
 }
@@ -250,7 +250,6 @@ impl BitAnd for CharReach {
     type Output = Self;
 
     #[inline]
-    #[must_use]
     fn bitand(self, rhs: Self) -> Self::Output {
         let mut rhs = rhs;
         rhs &= self;
@@ -269,7 +268,6 @@ impl BitOr for CharReach {
     type Output = Self;
 
     #[inline]
-    #[must_use]
     fn bitor(self, rhs: Self) -> Self::Output {
         let mut rhs = rhs;
         rhs |= self;
@@ -288,7 +286,6 @@ impl BitXor for CharReach {
     type Output = Self;
 
     #[inline]
-    #[must_use]
     fn bitxor(self, rhs: Self) -> Self::Output {
         let mut rhs = rhs;
         rhs ^= self;
@@ -307,7 +304,6 @@ impl Not for CharReach {
     type Output = Self;
 
     #[inline]
-    #[must_use]
     fn not(mut self) -> Self::Output {
         self.bits = !self.bits;
         self
@@ -328,18 +324,32 @@ pub(crate) fn make_caseless(cr: &mut CharReach) {
 /// # Panics
 ///
 /// Panics if `bits` has less than 32 elements.
-#[allow(dead_code)]
 pub(crate) fn fill_bitvector(cr: &CharReach, bits: &mut [u8]) {
     for b in bits[0..32].iter_mut() {
         *b = 0;
     }
     let mut next = cr.find_first();
     while let Some(pos) = next {
-        bits[(pos / 8) as usize] |= 1 << (pos & 8);
+        bits[(pos / 8) as usize] |= 1 << (pos % 8);
         next = cr.find_next(pos);
     }
 }
 
+/// Constructs a `CharReach` from a bitvector filled by [`fill_bitvector`].
+///
+/// # Panics
+///
+/// Panics if `bits` has less than 32 elements.
+pub(crate) fn from_bitvector(bits: &[u8]) -> CharReach {
+    let mut cr = CharReach::default();
+    for c in 0..=255_u8 {
+        if bits[(c / 8) as usize] & (1 << (c % 8)) != 0 {
+            cr.set(c);
+        }
+    }
+    cr
+}
+
 /// Generates and- and compare-masks for checking the char reach.
 ///
 /// Any character c in `cr` will satisfy `(c & and_mask) == cmp_mask`, where
@@ -366,7 +376,7 @@ pub(crate) fn make_and_cmp_mask(cr: &CharReach) -> (u8, u8) {
 
 #[cfg(test)]
 mod tests {
-    use super::CharReach;
+    use super::{fill_bitvector, from_bitvector, CharReach};
     use std::convert::TryInto;
 
     #[test]
@@ -509,10 +519,7 @@ mod tests {
     fn alpha_all() {
         for i in 0..=255 {
             let cr = CharReach::from_char(i);
-            assert_eq!(
-                cr.is_alphabetic(),
-                (b'A' <= i && i <= b'Z') || (b'a' <= i && i <= b'z')
-            );
+            assert_eq!(cr.is_alphabetic(), i.is_ascii_alphabetic());
         }
 
         assert!(!CharReach::dot().is_alphabetic());
@@ -557,8 +564,8 @@ mod tests {
                 cr.set(i);
                 cr.set(j);
 
-                let upper_lower = (i >= b'A' && i <= b'Z') && j == i ^ 0x20;
-                let lower_upper = (i >= b'a' && i <= b'z') && j == i ^ 0x20;
+                let upper_lower = i.is_ascii_uppercase() && j == i ^ 0x20;
+                let lower_upper = i.is_ascii_lowercase() && j == i ^ 0x20;
                 let caseless_pair = upper_lower | lower_upper;
 
                 assert_eq!(cr.is_caseless_char(), caseless_pair);
@@ -636,7 +643,7 @@ mod tests {
     #[test]
     fn find_empty() {
         assert_eq!(CharReach::default().find_first(), None);
-        assert_eq!(CharReach::default().find_next(u8::max_value()), None);
+        assert_eq!(CharReach::default().find_next(u8::MAX), None);
     }
 
     #[test]
@@ -691,9 +698,19 @@ mod tests {
         // More complex case.
         const BYTES: &[u8; 44] = b"\x01\x02\x03\x05\x06\x20!#$%&./0123568:;ABCDEFMNOPUYZbcdefwxyz";
         let cr = CharReach::from_bytes(BYTES);
-        for i in 0..BYTES.len() {
-            assert_eq!(cr.find_nth(i.try_into().unwrap()), Some(BYTES[i]));
+        for (i, &b) in BYTES.iter().enumerate() {
+            assert_eq!(cr.find_nth(i.try_into().unwrap()), Some(b));
         }
         assert_eq!(cr.find_nth(BYTES.len().try_into().unwrap()), None);
     }
+
+    #[test]
+    fn bitvector() {
+        let cr = CharReach::from_bytes(b"\x00\x07\x08AZaz\xff");
+        let mut bits = [0_u8; 32];
+        fill_bitvector(&cr, &mut bits);
+        assert_eq!(bits[0], 0x81);
+        assert_eq!(bits[1], 0x01);
+        assert_eq!(from_bitvector(&bits), cr);
+    }
 }
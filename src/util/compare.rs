@@ -1,9 +1,9 @@
 pub fn myisupper(c: u8) -> bool {
-    (b'A'..=b'Z').contains(&c)
+    c.is_ascii_uppercase()
 }
 
 pub fn myislower(c: u8) -> bool {
-    (b'a'..=b'z').contains(&c)
+    c.is_ascii_lowercase()
 }
 
 #[must_use]
@@ -42,6 +42,12 @@ impl CompileError {
         }
     }
 
+    /// Returns the category of the error.
+    #[must_use]
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
     pub(crate) fn set_expression_index(&mut self, index: u32) {
         debug_assert!(self.index.is_none());
         self.index = Some(index);
@@ -19,6 +19,19 @@ impl Depth {
     pub(crate) fn infinity() -> Self {
         Self { val: INFINITY }
     }
+
+    pub(crate) fn is_finite(self) -> bool {
+        self.val <= MAX_VALUE
+    }
+
+    /// Returns the depth as a count, or `None` if it is not finite.
+    pub(crate) fn value(self) -> Option<u32> {
+        if self.is_finite() {
+            Some(self.val)
+        } else {
+            None
+        }
+    }
 }
 
 impl TryFrom<u32> for Depth {
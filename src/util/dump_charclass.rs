@@ -22,7 +22,7 @@ fn describe_char<W: Write>(f: &mut W, c: u8, out_type: CcOutput) -> Result<(), E
         r#"\"#
     };
 
-    if 0x21 <= c && c < 0x7F && c != b'\\' {
+    if (0x21..0x7F).contains(&c) && c != b'\\' {
         if ESCAPED.contains(&c) {
             f.write_str(backslash)?;
             f.write_char(c.into())
@@ -113,11 +113,7 @@ fn extract_mnemonic<W: Write>(
 }
 
 fn contiguous_range(cr: &CharReach) -> Option<(u8, u8)> {
-    let first = if let Some(pos) = cr.find_first() {
-        pos
-    } else {
-        return None;
-    };
+    let first = cr.find_first()?;
     let mut last = first;
     while let Some(c) = cr.find_next(last) {
         if c != last + 1 {
@@ -137,7 +133,7 @@ fn describe_class_int<W: Write>(
     // Approx size of output
     let mut i = 0;
     // One we can break
-    let mut cr = incr.clone();
+    let mut cr = *incr;
 
     // If we can be rendered as a single range, do it.
     if let Some((first, last)) = contiguous_range(&cr) {
@@ -175,10 +171,8 @@ fn describe_class_int<W: Write>(
             }
             c_last = c;
         }
-        if c_last == 0xff {
-            describe_range(f, c_start, c_last, out_type)?;
-            i += 1;
-        }
+        describe_range(f, c_start, c_last, out_type)?;
+        i += 1;
     }
     Ok(i)
 }
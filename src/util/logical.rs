@@ -0,0 +1,111 @@
+use crate::util::serialize::{CorruptError, Reader, Writer};
+use crate::util::ReportId;
+use bit_vec::BitVec;
+
+/// Instruction of a logical combination, which are evaluated in postfix
+/// order.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum LogicalOp {
+    /// Pushes whether the sub-expression with this logical key has matched.
+    Key(u32),
+    /// Negates the value on top of the stack.
+    Not,
+    /// Replaces the two values on top of the stack with their conjunction.
+    And,
+    /// Replaces the two values on top of the stack with their disjunction.
+    Or,
+}
+
+/// A logical combination of the matches of other expressions, reported at
+/// end of data if it holds.
+#[derive(Clone, Debug)]
+pub(crate) struct CombInfo {
+    /// The external ID delivered to the user.
+    pub(crate) id: ReportId,
+
+    /// Don't report this match to the user.
+    pub(crate) quiet: bool,
+
+    /// The combination, in postfix order.
+    pub(crate) ops: Vec<LogicalOp>,
+}
+
+impl CombInfo {
+    /// Returns whether the combination holds, given the logical keys of the
+    /// sub-expressions that have matched.
+    pub(crate) fn eval(&self, logical: &BitVec) -> bool {
+        let mut stack: Vec<bool> = Vec::new();
+        for op in &self.ops {
+            let value = match op {
+                LogicalOp::Key(lkey) => logical[*lkey as usize],
+                LogicalOp::Not => !stack.pop().expect("well-formed combination"),
+                LogicalOp::And | LogicalOp::Or => {
+                    let r = stack.pop().expect("well-formed combination");
+                    let l = stack.pop().expect("well-formed combination");
+                    if *op == LogicalOp::And {
+                        l && r
+                    } else {
+                        l || r
+                    }
+                }
+            };
+            stack.push(value);
+        }
+        stack.pop().expect("well-formed combination")
+    }
+
+    pub(crate) fn serialize(&self, w: &mut Writer) {
+        w.put_u32(self.id);
+        w.put_bool(self.quiet);
+        w.put_len(self.ops.len());
+        for op in &self.ops {
+            match op {
+                LogicalOp::Key(lkey) => {
+                    w.put_u8(0);
+                    w.put_u32(*lkey);
+                }
+                LogicalOp::Not => w.put_u8(1),
+                LogicalOp::And => w.put_u8(2),
+                LogicalOp::Or => w.put_u8(3),
+            }
+        }
+    }
+
+    /// Reads a combination of sub-expressions whose logical keys are below
+    /// `num_lkeys`.
+    pub(crate) fn deserialize(r: &mut Reader, num_lkeys: u32) -> Result<Self, CorruptError> {
+        let id = r.u32()?;
+        let quiet = r.bool()?;
+        let num_ops = r.len(1)?;
+        let mut ops = Vec::with_capacity(num_ops);
+        // Depth of the evaluation stack, which must end with a single value.
+        let mut depth = 0_usize;
+        for _ in 0..num_ops {
+            let op = match r.u8()? {
+                0 => {
+                    let lkey = r.u32()?;
+                    if lkey >= num_lkeys {
+                        return Err(CorruptError);
+                    }
+                    depth += 1;
+                    LogicalOp::Key(lkey)
+                }
+                1 if depth >= 1 => LogicalOp::Not,
+                2 if depth >= 2 => {
+                    depth -= 1;
+                    LogicalOp::And
+                }
+                3 if depth >= 2 => {
+                    depth -= 1;
+                    LogicalOp::Or
+                }
+                _ => return Err(CorruptError),
+            };
+            ops.push(op);
+        }
+        if depth != 1 {
+            return Err(CorruptError);
+        }
+        Ok(Self { id, quiet, ops })
+    }
+}
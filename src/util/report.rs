@@ -1,22 +1,119 @@
-#[derive(Clone, Hash, PartialEq, Eq)]
+use crate::util::serialize::{CorruptError, Reader, Writer};
+use crate::util::ReportId;
+
+/// Type of a report.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub(crate) enum ReportType {
+    /// Callback with the match's end offset.
+    ExternalCallback,
+    /// Callback with a start of match a fixed distance before the end.
+    ExternalCallbackSomRel,
+}
+
+/// A report raised by the engine and delivered to the user.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub(crate) struct Report {
+    /// Type of the report.
+    pub(crate) kind: ReportType,
+
+    /// Don't report this match to the user.
+    pub(crate) quiet: bool,
+
+    /// Adjustment to apply to the match offset when reporting.
+    pub(crate) offset_adjust: i32,
+
+    /// Exhaustion key, if the report is exhaustible.
+    pub(crate) ekey: Option<u32>,
+
+    /// Logical key, if the report is that of a sub-expression of a logical
+    /// combination.
+    pub(crate) lkey: Option<u32>,
+
     /// Minimum match length (start of match to current offset).
     pub(crate) min_length: u64,
+
+    /// Distance from the end of the match to its start, for
+    /// `ExternalCallbackSomRel` reports.
+    pub(crate) som_distance: u64,
+
+    /// The external ID delivered to the user.
+    pub(crate) onmatch: ReportId,
+}
+
+impl Report {
+    pub(crate) fn serialize(&self, w: &mut Writer) {
+        w.put_u8(match self.kind {
+            ReportType::ExternalCallback => 0,
+            ReportType::ExternalCallbackSomRel => 1,
+        });
+        w.put_bool(self.quiet);
+        w.put_i32(self.offset_adjust);
+        for key in &[self.ekey, self.lkey] {
+            match key {
+                Some(key) => {
+                    w.put_bool(true);
+                    w.put_u32(*key);
+                }
+                None => w.put_bool(false),
+            }
+        }
+        w.put_u64(self.min_length);
+        w.put_u64(self.som_distance);
+        w.put_u32(self.onmatch);
+    }
+
+    pub(crate) fn deserialize(r: &mut Reader) -> Result<Self, CorruptError> {
+        let kind = match r.u8()? {
+            0 => ReportType::ExternalCallback,
+            1 => ReportType::ExternalCallbackSomRel,
+            _ => return Err(CorruptError),
+        };
+        let quiet = r.bool()?;
+        let offset_adjust = r.i32()?;
+        let ekey = if r.bool()? { Some(r.u32()?) } else { None };
+        let lkey = if r.bool()? { Some(r.u32()?) } else { None };
+        Ok(Self {
+            kind,
+            quiet,
+            offset_adjust,
+            ekey,
+            lkey,
+            min_length: r.u64()?,
+            som_distance: r.u64()?,
+            onmatch: r.u32()?,
+        })
+    }
+
+    fn new(kind: ReportType, onmatch: ReportId) -> Self {
+        Self {
+            kind,
+            quiet: false,
+            offset_adjust: 0,
+            ekey: None,
+            lkey: None,
+            min_length: 0,
+            som_distance: 0,
+            onmatch,
+        }
+    }
 }
 
 pub(crate) fn make_e_callback(
-    _report: u32,
-    _offset_adjust: i32,
-    _ekey: Option<u32>,
-    _quiet: bool,
+    report: u32,
+    offset_adjust: i32,
+    ekey: Option<u32>,
+    quiet: bool,
 ) -> Report {
-    Report { min_length: 0 }
+    let mut ir = Report::new(ReportType::ExternalCallback, report);
+    ir.offset_adjust = offset_adjust;
+    ir.ekey = ekey;
+    ir.quiet = quiet;
+    ir
 }
 
-pub(crate) fn make_som_relative_callback(
-    _report: u32,
-    _offset_adjust: i32,
-    _distance: u64,
-) -> Report {
-    Report { min_length: 0 }
+pub(crate) fn make_som_relative_callback(report: u32, offset_adjust: i32, distance: u64) -> Report {
+    let mut ir = Report::new(ReportType::ExternalCallbackSomRel, report);
+    ir.offset_adjust = offset_adjust;
+    ir.som_distance = distance;
+    ir
 }
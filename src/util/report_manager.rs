@@ -1,4 +1,6 @@
-use crate::util::{Report, ReportId, S64a};
+use crate::compiler::ExpressionInfo;
+use crate::parser::ParsedLogical;
+use crate::util::{make_e_callback, Report, ReportId, S64a};
 use crate::{CompileError, ErrorKind, Grey};
 use std::collections::{hash_map::Entry, BTreeMap, HashMap};
 use std::convert::TryInto;
@@ -38,6 +40,9 @@ pub(crate) struct ReportManager<'a> {
     /// Whether database is globally exhaustible (all patterns must b highlander
     /// for this to be `true`).
     global_exhaust: bool,
+
+    /// Logical combinations and their sub-expressions.
+    pub(crate) pl: ParsedLogical,
 }
 
 impl<'a> ReportManager<'a> {
@@ -49,6 +54,7 @@ impl<'a> ReportManager<'a> {
             external_id_map: HashMap::default(),
             to_exhaustible_key_map: BTreeMap::default(),
             global_exhaust: true,
+            pl: ParsedLogical::default(),
         }
     }
 
@@ -87,6 +93,42 @@ impl<'a> ReportManager<'a> {
         self.report_ids.len()
     }
 
+    /// Returns all reports, indexed by ID.
+    pub(crate) fn reports(&self) -> &[Report] {
+        &self.report_ids
+    }
+
+    /// Builds the report for a match of the given expression, `offset_adjust`
+    /// bytes away from the current offset.
+    pub(crate) fn get_basic_internal_report(
+        &mut self,
+        expr: &ExpressionInfo,
+        offset_adjust: i32,
+    ) -> Result<Report, CompileError> {
+        // Validate that we are not violating highlander constraints.
+        self.register_ext_report(
+            expr.report,
+            ExternalReportInfo::new(
+                expr.highlander,
+                expr.index.try_into().expect("too many expressions"),
+            ),
+        )?;
+
+        // All patterns with the same report ID share an ekey.
+        let ekey = if expr.highlander {
+            Some(self.get_exhaustible_key(expr.report))
+        } else {
+            None
+        };
+
+        Ok(make_e_callback(
+            expr.report,
+            offset_adjust,
+            ekey,
+            expr.quiet,
+        ))
+    }
+
     /// Registers an external report and validate that we are not violating
     /// highlander constraints (which will cause an exception to be thrown).
     pub(crate) fn register_ext_report(
@@ -130,6 +172,19 @@ impl<'a> ReportManager<'a> {
         Ok(())
     }
 
+    /// Number of exhaustion keys assigned.
+    pub(crate) fn num_ekeys(&self) -> u32 {
+        self.to_exhaustible_key_map
+            .len()
+            .try_into()
+            .expect("too many expressions")
+    }
+
+    /// Whether every pattern of the database is exhaustible.
+    pub(crate) fn global_exhaust(&self) -> bool {
+        self.global_exhaust
+    }
+
     /// Fetch the ekey associated with the given expression index, assigning one
     /// if necessary.
     ///
@@ -0,0 +1,124 @@
+//! Helpers for the byte format of serialized databases.
+//!
+//! All integers are stored little-endian; sequences are prefixed with their
+//! length as a `u32`.
+
+use std::convert::TryInto;
+
+/// Error raised when serialized bytes cannot be decoded.
+#[derive(Debug)]
+pub(crate) struct CorruptError;
+
+/// Appends encoded values to a byte buffer.
+#[derive(Default)]
+pub(crate) struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+
+    pub(crate) fn put_u8(&mut self, v: u8) {
+        self.buf.push(v);
+    }
+
+    pub(crate) fn put_bool(&mut self, v: bool) {
+        self.put_u8(v.into());
+    }
+
+    pub(crate) fn put_u32(&mut self, v: u32) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub(crate) fn put_i32(&mut self, v: i32) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub(crate) fn put_u64(&mut self, v: u64) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub(crate) fn put_len(&mut self, len: usize) {
+        self.put_u32(len.try_into().expect("sequence too long to serialize"));
+    }
+
+    pub(crate) fn put_bytes(&mut self, v: &[u8]) {
+        self.buf.extend_from_slice(v);
+    }
+
+    pub(crate) fn put_u32_slice(&mut self, v: &[u32]) {
+        self.put_len(v.len());
+        for &x in v {
+            self.put_u32(x);
+        }
+    }
+}
+
+/// Decodes values from a byte buffer.
+pub(crate) struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    /// Returns `true` if every byte has been consumed.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    pub(crate) fn bytes(&mut self, n: usize) -> Result<&'a [u8], CorruptError> {
+        if self.buf.len() < n {
+            return Err(CorruptError);
+        }
+        let (head, tail) = self.buf.split_at(n);
+        self.buf = tail;
+        Ok(head)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, CorruptError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub(crate) fn bool(&mut self) -> Result<bool, CorruptError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(CorruptError),
+        }
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, CorruptError> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_le_bytes(b.try_into().expect("four bytes")))
+    }
+
+    pub(crate) fn i32(&mut self) -> Result<i32, CorruptError> {
+        let b = self.bytes(4)?;
+        Ok(i32::from_le_bytes(b.try_into().expect("four bytes")))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, CorruptError> {
+        let b = self.bytes(8)?;
+        Ok(u64::from_le_bytes(b.try_into().expect("eight bytes")))
+    }
+
+    /// Reads a sequence length, checking that the rest of the buffer can hold
+    /// that many elements of at least `min_elem_size` bytes.
+    pub(crate) fn len(&mut self, min_elem_size: usize) -> Result<usize, CorruptError> {
+        let len = self.u32()? as usize;
+        if len.saturating_mul(min_elem_size) > self.buf.len() {
+            return Err(CorruptError);
+        }
+        Ok(len)
+    }
+
+    pub(crate) fn u32_vec(&mut self) -> Result<Vec<u32>, CorruptError> {
+        let len = self.len(4)?;
+        (0..len).map(|_| self.u32()).collect()
+    }
+}
@@ -2,7 +2,7 @@ use super::compare::{mytoupper, ourisalpha};
 use bit_vec::BitVec;
 use std::cmp::Ordering;
 
-#[derive(Default, Eq)]
+#[derive(Clone, Default, Eq)]
 pub struct Ue2Literal {
     s: Vec<u8>,
     nocase: BitVec,
@@ -10,7 +10,7 @@ pub struct Ue2Literal {
 
 pub struct Ue2LiteralElem {
     pub c: u8,
-    nocase: bool,
+    pub nocase: bool,
 }
 
 pub struct Ue2LiteralIter<'a> {
@@ -35,7 +35,7 @@ impl Ue2Literal {
     }
 
     #[must_use]
-    pub fn iter(&self) -> Ue2LiteralIter {
+    pub fn iter(&self) -> Ue2LiteralIter<'_> {
         Ue2LiteralIter { lit: self, idx: 0 }
     }
 
@@ -63,21 +63,18 @@ impl<'a> IntoIterator for &'a Ue2Literal {
     type Item = Ue2LiteralElem;
     type IntoIter = Ue2LiteralIter<'a>;
 
-    #[must_use]
     fn into_iter(self) -> Self::IntoIter {
         self.iter()
     }
 }
 
 impl PartialEq for Ue2Literal {
-    #[must_use]
     fn eq(&self, other: &Self) -> bool {
         self.s == other.s
     }
 }
 
 impl Ord for Ue2Literal {
-    #[must_use]
     fn cmp(&self, other: &Self) -> Ordering {
         let ordering = self.s.cmp(&other.s);
         if ordering == Ordering::Equal {
@@ -89,7 +86,6 @@ impl Ord for Ue2Literal {
 }
 
 impl PartialOrd for Ue2Literal {
-    #[must_use]
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
@@ -127,6 +123,6 @@ mod test {
         let empty2 = Ue2Literal::default();
 
         assert!(empty1 == empty2);
-        assert!(!(empty1 != empty2));
+        assert!((empty1 == empty2));
     }
 }
@@ -1,3 +1,87 @@
+use super::ucp_table::{UCP_GENERAL_CATEGORIES, UCP_SCRIPTS};
+use super::CodePointSet;
+
 pub const UTF_TWO_BYTE_HEADER: u8 = 0xc0; // 110x_xxxx
 
 pub const UTF_CONT_MAX: u8 = UTF_TWO_BYTE_HEADER - 1;
+
+pub const MAX_UNICODE: u32 = 0x10_ffff;
+
+/// Looks up a range table by name.
+fn find_table(
+    table: &[(&str, &'static [(u32, u32)])],
+    name: &str,
+) -> Option<&'static [(u32, u32)]> {
+    table
+        .binary_search_by_key(&name, |&(n, _)| n)
+        .ok()
+        .map(|i| table[i].1)
+}
+
+/// Returns the code points with the Unicode property `name`, as written in
+/// `\p{..}`: `Any`, `L&`, a general category or its one-letter group, or a
+/// script.
+pub(crate) fn ucp_property(name: &str) -> Option<CodePointSet> {
+    let mut cps = CodePointSet::default();
+    match name {
+        "Any" => cps.set_range(0, MAX_UNICODE),
+        "L&" => cps |= &CodePointSet::from_ranges(find_table(UCP_GENERAL_CATEGORIES, "LC")?),
+        _ if name.len() == 1 => {
+            let mut found = false;
+            for &(gc, ranges) in UCP_GENERAL_CATEGORIES {
+                // "LC" is a subset of "L", and not a category of its own.
+                if gc.starts_with(name) && gc != "LC" {
+                    cps |= &CodePointSet::from_ranges(ranges);
+                    found = true;
+                }
+            }
+            if !found {
+                return None;
+            }
+        }
+        _ => {
+            let ranges = find_table(UCP_GENERAL_CATEGORIES, name)
+                .filter(|_| name != "LC")
+                .or_else(|| find_table(UCP_SCRIPTS, name))?;
+            cps |= &CodePointSet::from_ranges(ranges);
+        }
+    }
+    Some(cps)
+}
+
+/// Returns the code points matched by `\w` in UCP mode: letters, numbers and
+/// the underscore.
+pub(crate) fn ucp_word_chars() -> CodePointSet {
+    let mut cps = CodePointSet::default();
+    for name in &["L", "N"] {
+        cps |= &ucp_property(name).expect("known Unicode property");
+    }
+    cps.set(u32::from(b'_'));
+    cps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ucp_property;
+
+    #[test]
+    fn properties() {
+        let has = |name: &str, c: char| {
+            let cps = ucp_property(name).expect("known property");
+            cps.ranges()
+                .iter()
+                .any(|&(lo, hi)| lo <= u32::from(c) && u32::from(c) <= hi)
+        };
+        assert!(has("Greek", '\u{3b1}'));
+        assert!(!has("Greek", 'a'));
+        assert!(has("Lu", 'A'));
+        assert!(!has("Lu", 'a'));
+        assert!(has("L&", 'a'));
+        assert!(!has("L&", '\u{5d0}'));
+        assert!(has("L", '\u{5d0}'));
+        assert!(has("Any", '\u{10ffff}'));
+        assert!(ucp_property("LC").is_none());
+        assert!(ucp_property("X").is_none());
+        assert!(ucp_property("Klingon").is_none());
+    }
+}
@@ -0,0 +1,276 @@
+use super::ucp_table::UCP_CASELESS;
+use super::unicode_def::MAX_UNICODE;
+use std::fmt;
+use std::ops::{BitOrAssign, SubAssign};
+
+/// Set of Unicode code points, stored as sorted, disjoint and non-adjacent
+/// inclusive ranges.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct CodePointSet {
+    ranges: Vec<(u32, u32)>,
+}
+
+impl CodePointSet {
+    /// Creates the set of the code points in `ranges`, given as inclusive
+    /// ranges in any order.
+    pub(crate) fn from_ranges(ranges: &[(u32, u32)]) -> Self {
+        let mut cps = Self::default();
+        cps |= &Self {
+            ranges: ranges.to_vec(),
+        };
+        cps
+    }
+
+    pub(crate) fn set(&mut self, c: u32) {
+        self.set_range(c, c);
+    }
+
+    /// Adds the code points from `from` to `to` inclusive.
+    pub(crate) fn set_range(&mut self, from: u32, to: u32) {
+        debug_assert!(from <= to && to <= MAX_UNICODE);
+        let mut from = from;
+        let mut to = to;
+        // Merge with every range that overlaps or touches the new one.
+        let start = self
+            .ranges
+            .iter()
+            .position(|&(_, hi)| hi.saturating_add(1) >= from)
+            .unwrap_or(self.ranges.len());
+        let mut end = start;
+        while end < self.ranges.len() && self.ranges[end].0 <= to.saturating_add(1) {
+            from = from.min(self.ranges[end].0);
+            to = to.max(self.ranges[end].1);
+            end += 1;
+        }
+        self.ranges.splice(start..end, Some((from, to)));
+    }
+
+    pub(crate) fn unset(&mut self, c: u32) {
+        self.unset_range(c, c);
+    }
+
+    /// Removes the code points from `from` to `to` inclusive.
+    pub(crate) fn unset_range(&mut self, from: u32, to: u32) {
+        debug_assert!(from <= to);
+        let mut out = Vec::with_capacity(self.ranges.len() + 1);
+        for &(lo, hi) in &self.ranges {
+            if hi < from || lo > to {
+                out.push((lo, hi));
+                continue;
+            }
+            if lo < from {
+                out.push((lo, from - 1));
+            }
+            if hi > to {
+                out.push((to + 1, hi));
+            }
+        }
+        self.ranges = out;
+    }
+
+    /// Replaces the set with its complement among all code points.
+    pub(crate) fn flip(&mut self) {
+        let mut out = Vec::with_capacity(self.ranges.len() + 1);
+        let mut next = 0;
+        for &(lo, hi) in &self.ranges {
+            if lo > next {
+                out.push((next, lo - 1));
+            }
+            next = hi + 1;
+        }
+        if next <= MAX_UNICODE {
+            out.push((next, MAX_UNICODE));
+        }
+        self.ranges = out;
+    }
+
+    /// Adds the code points equal to those in the set under Unicode simple
+    /// case folding.
+    pub(crate) fn make_caseless(&mut self) {
+        let mut other = Vec::new();
+        for &(lo, hi) in &self.ranges {
+            // No code point folds to zero, so this finds the first pair for
+            // `lo` or above.
+            let start = UCP_CASELESS.binary_search(&(lo, 0)).unwrap_or_else(|i| i);
+            for &(c, folded) in &UCP_CASELESS[start..] {
+                if c > hi {
+                    break;
+                }
+                other.push((folded, folded));
+            }
+        }
+        *self |= &Self { ranges: other };
+    }
+
+    pub(crate) fn ranges(&self) -> &[(u32, u32)] {
+        &self.ranges
+    }
+}
+
+impl BitOrAssign<&CodePointSet> for CodePointSet {
+    fn bitor_assign(&mut self, other: &CodePointSet) {
+        let mut all: Vec<_> = self.ranges.iter().chain(&other.ranges).copied().collect();
+        all.sort_unstable();
+        let mut out: Vec<(u32, u32)> = Vec::with_capacity(all.len());
+        for (lo, hi) in all {
+            match out.last_mut() {
+                Some(last) if lo <= last.1.saturating_add(1) => last.1 = last.1.max(hi),
+                _ => out.push((lo, hi)),
+            }
+        }
+        self.ranges = out;
+    }
+}
+
+impl SubAssign<&CodePointSet> for CodePointSet {
+    fn sub_assign(&mut self, other: &CodePointSet) {
+        for &(lo, hi) in &other.ranges {
+            self.unset_range(lo, hi);
+        }
+    }
+}
+
+impl fmt::Display for CodePointSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, &(lo, hi)) in self.ranges.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            if lo == hi {
+                write!(f, "U+{:04X}", lo)?;
+            } else {
+                write!(f, "U+{:04X}-U+{:04X}", lo, hi)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the byte ranges matching the UTF-8 encodings of the code points
+/// from `from` to `to` inclusive.
+///
+/// Each entry is a sequence of byte ranges, one per byte of the encoding;
+/// surrogates are skipped as they cannot be encoded.
+pub(crate) fn utf8_sequences(from: u32, to: u32) -> Vec<Vec<(u8, u8)>> {
+    const MAX_SCALAR: [u32; 3] = [0x7f, 0x7ff, 0xffff];
+
+    let mut out = Vec::new();
+    let mut stack = vec![(from, to)];
+    'outer: while let Some((s, mut e)) = stack.pop() {
+        loop {
+            // Skip the surrogate range.
+            if s <= 0xdfff && e >= 0xd800 {
+                if e > 0xdfff {
+                    stack.push((0xe000, e));
+                }
+                if s >= 0xd800 {
+                    continue 'outer;
+                }
+                e = 0xd7ff;
+            }
+
+            // Split into ranges whose encodings have the same length.
+            if let Some(&max) = MAX_SCALAR.iter().find(|&&max| s <= max && max < e) {
+                stack.push((max + 1, e));
+                e = max;
+                continue;
+            }
+
+            // Split until each byte of the encoding covers a contiguous range.
+            let mut split = false;
+            for i in 1..4 {
+                let m = (1 << (6 * i)) - 1;
+                if s & !m != e & !m {
+                    if s & m != 0 {
+                        stack.push(((s | m) + 1, e));
+                        e = s | m;
+                        split = true;
+                        break;
+                    }
+                    if e & m != m {
+                        stack.push((e & !m, e));
+                        e = (e & !m) - 1;
+                        split = true;
+                        break;
+                    }
+                }
+            }
+            if split {
+                continue;
+            }
+
+            let mut sb = [0; 4];
+            let mut eb = [0; 4];
+            let sb = encode(s, &mut sb);
+            let eb = encode(e, &mut eb);
+            out.push(sb.iter().zip(eb).map(|(&lo, &hi)| (lo, hi)).collect());
+            break;
+        }
+    }
+    out
+}
+
+fn encode(c: u32, buf: &mut [u8; 4]) -> &[u8] {
+    std::char::from_u32(c)
+        .expect("surrogates are skipped")
+        .encode_utf8(buf)
+        .as_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{utf8_sequences, CodePointSet};
+
+    #[test]
+    fn set_and_unset() {
+        let mut cps = CodePointSet::default();
+        cps.set_range(10, 20);
+        cps.set_range(30, 40);
+        cps.set(21);
+        assert_eq!(cps.ranges(), &[(10, 21), (30, 40)]);
+        cps.set_range(15, 35);
+        assert_eq!(cps.ranges(), &[(10, 40)]);
+        cps.unset(12);
+        assert_eq!(cps.ranges(), &[(10, 11), (13, 40)]);
+        cps.flip();
+        assert_eq!(cps.ranges(), &[(0, 9), (12, 12), (41, 0x10_ffff)]);
+    }
+
+    #[test]
+    fn caseless() {
+        let mut cps = CodePointSet::default();
+        cps.set(u32::from('k'));
+        cps.set(0x3c3);
+        cps.make_caseless();
+        assert_eq!(
+            cps.ranges(),
+            &[
+                (0x4b, 0x4b),
+                (0x6b, 0x6b),
+                (0x3a3, 0x3a3),
+                (0x3c2, 0x3c3),
+                (0x212a, 0x212a)
+            ]
+        );
+    }
+
+    #[test]
+    fn sequences() {
+        assert_eq!(utf8_sequences(0, 0x7f), vec![vec![(0, 0x7f)]]);
+        assert_eq!(
+            utf8_sequences(0x7f, 0x800),
+            vec![
+                vec![(0x7f, 0x7f)],
+                vec![(0xc2, 0xdf), (0x80, 0xbf)],
+                vec![(0xe0, 0xe0), (0xa0, 0xa0), (0x80, 0x80)],
+            ]
+        );
+        assert_eq!(
+            utf8_sequences(0xd000, 0xe000),
+            vec![
+                vec![(0xed, 0xed), (0x80, 0x9f), (0x80, 0xbf)],
+                vec![(0xee, 0xee), (0x80, 0x80), (0x80, 0x80)],
+            ]
+        );
+    }
+}
@@ -0,0 +1,165 @@
+#!/usr/bin/env python3
+"""Generates src/util/ucp_table.rs from the Unicode Character Database.
+
+Usage: tools/gen_ucp_table.py UCD_DIR > src/util/ucp_table.rs
+
+UCD_DIR holds UnicodeData.txt, Scripts.txt and CaseFolding.txt from
+https://www.unicode.org/Public/14.0.0/ucd/. Bump UNICODE_VERSION along with
+the files when moving to a later version.
+"""
+
+import os
+import sys
+
+UNICODE_VERSION = "14.0.0"
+MAX_UNICODE = 0x10FFFF
+LINE_WIDTH = 100
+
+
+def read_fields(path):
+    """Yields the semicolon-separated fields of each line of a UCD file."""
+    with open(path, encoding="utf-8") as f:
+        for line in f:
+            line = line.split("#", 1)[0].strip()
+            if line:
+                yield [field.strip() for field in line.split(";")]
+
+
+def parse_range(text):
+    """Parses a code point or a range of them, like `0041..005A`."""
+    if ".." in text:
+        lo, hi = text.split("..")
+        return int(lo, 16), int(hi, 16)
+    c = int(text, 16)
+    return c, c
+
+
+def to_ranges(values, name):
+    """Returns the inclusive ranges of the code points whose value is `name`."""
+    ranges = []
+    for c, value in enumerate(values):
+        if value != name:
+            continue
+        if ranges and ranges[-1][1] + 1 == c:
+            ranges[-1][1] = c
+        else:
+            ranges.append([c, c])
+    return ranges
+
+
+def merge(ranges):
+    """Sorts inclusive ranges and merges those overlapping or touching."""
+    out = []
+    for lo, hi in sorted(ranges):
+        if out and lo <= out[-1][1] + 1:
+            out[-1][1] = max(out[-1][1], hi)
+        else:
+            out.append([lo, hi])
+    return out
+
+
+def general_categories(ucd):
+    """Returns the ranges of each general category, plus `LC` for cased
+    letters."""
+    values = ["Cn"] * (MAX_UNICODE + 1)
+    first = None
+    for fields in read_fields(os.path.join(ucd, "UnicodeData.txt")):
+        c = int(fields[0], 16)
+        name, gc = fields[1], fields[2]
+        if name.endswith(", First>"):
+            first = c
+            continue
+        if name.endswith(", Last>"):
+            values[first : c + 1] = [gc] * (c + 1 - first)
+        else:
+            values[c] = gc
+
+    tables = {gc: to_ranges(values, gc) for gc in set(values)}
+    tables["LC"] = merge(tables["Lu"] + tables["Ll"] + tables["Lt"])
+    return tables
+
+
+def scripts(ucd):
+    """Returns the ranges of each script, with the code points not listed
+    in `Unknown`."""
+    values = ["Unknown"] * (MAX_UNICODE + 1)
+    for fields in read_fields(os.path.join(ucd, "Scripts.txt")):
+        lo, hi = parse_range(fields[0])
+        values[lo : hi + 1] = [fields[1]] * (hi + 1 - lo)
+    return {script: to_ranges(values, script) for script in set(values)}
+
+
+def caseless_pairs(ucd):
+    """Returns the pairs of distinct code points with the same simple case
+    folding."""
+    classes = {}
+    for fields in read_fields(os.path.join(ucd, "CaseFolding.txt")):
+        if fields[1] not in ("C", "S"):
+            continue
+        c, folded = int(fields[0], 16), int(fields[2], 16)
+        classes.setdefault(folded, {folded}).add(c)
+    return sorted(
+        (a, b) for members in classes.values() for a in members for b in members if a != b
+    )
+
+
+def wrap(items, indent):
+    """Fills lines of at most LINE_WIDTH columns with `items`."""
+    lines = []
+    line = ""
+    for item in items:
+        if line and len(indent) + len(line) + 1 + len(item) > LINE_WIDTH:
+            lines.append(indent + line)
+            line = ""
+        line = line + " " + item if line else item
+    if line:
+        lines.append(indent + line)
+    return lines
+
+
+def pair(lo, hi):
+    return "(0x{:x}, 0x{:x}),".format(lo, hi)
+
+
+def range_table(name, doc, tables):
+    lines = ["/// " + doc, "pub(crate) const {}: &[(&str, &[(u32, u32)])] = &[".format(name)]
+    for key in sorted(tables):
+        lines.append('    ("{}", &['.format(key))
+        lines.extend(wrap([pair(lo, hi) for lo, hi in tables[key]], " " * 8))
+        lines.append("    ]),")
+    lines.append("];")
+    return lines
+
+
+def main():
+    if len(sys.argv) != 2:
+        sys.exit("usage: {} UCD_DIR".format(sys.argv[0]))
+    ucd = sys.argv[1]
+
+    lines = [
+        "//! Unicode general category, script and case folding tables, from version",
+        "//! {} of the Unicode Character Database.".format(UNICODE_VERSION),
+        "//!",
+        "//! This file is generated by tools/gen_ucp_table.py; do not edit it by hand.",
+        "",
+    ]
+    lines += range_table(
+        "UCP_GENERAL_CATEGORIES",
+        "Ranges of each two-letter general category, sorted by name.",
+        general_categories(ucd),
+    )
+    lines.append("")
+    lines += range_table("UCP_SCRIPTS", "Ranges of each script, sorted by name.", scripts(ucd))
+    lines += [
+        "",
+        "/// Pairs of distinct code points that are equal under simple case folding,",
+        "/// sorted.",
+        "pub(crate) const UCP_CASELESS: &[(u32, u32)] = &[",
+    ]
+    lines += wrap([pair(a, b) for a, b in caseless_pairs(ucd)], " " * 4)
+    lines.append("];")
+    sys.stdout.write("\n".join(lines) + "\n")
+
+
+if __name__ == "__main__":
+    main()